@@ -49,4 +49,28 @@ impl<'a, T> DormantMutRef<'a, T> {
     // SAFETY: our own safety conditions imply this reference is again unique.
     &mut *self.ptr.as_ptr()
   }
+
+  /// Borrows a new shared reference from the unique borrow initially captured.
+  ///
+  /// # Safety
+  ///
+  /// The reborrow must have ended, i.e., the reference returned by `new` and
+  /// all pointers and references derived from it, must not be used to mutate
+  /// while the returned reference is in use. Reading through them is allowed.
+  pub unsafe fn reborrow_shared(&self) -> &'a T {
+    // SAFETY: our own safety conditions imply nothing mutates through other
+    // references while this one is in use.
+    &*self.ptr.as_ptr()
+  }
+
+  /// Borrows a new mutable reference from the unique borrow initially captured.
+  ///
+  /// # Safety
+  ///
+  /// The reborrow must have ended, i.e., the reference returned by `new` and
+  /// all pointers and references derived from it, must not be used anymore.
+  pub unsafe fn reborrow(&mut self) -> &'a mut T {
+    // SAFETY: our own safety conditions imply this reference is again unique.
+    &mut *self.ptr.as_ptr()
+  }
 }
@@ -0,0 +1,245 @@
+//! Bottom-up construction of a tree from entries sorted by key bytes.
+//!
+//! Builder keeps open nodes on the right-most path of the tree. For each new key, nodes deeper
+//! than its common prefix with the previous key can't get more children, so they're allocated
+//! with their final class and partial key, and attached to their parents.
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::ptr::NonNull;
+
+use allocator_api2::alloc::Allocator;
+
+use crate::common_len;
+use crate::key::ARTKey;
+use crate::map::{ARTMap, IntoIter};
+use crate::marker::{Leaf, Mut};
+use crate::node::{new_internal_node, BoxedNode, LeafNode, NodeArena, NodeRef};
+
+/// Error returned by bulk loading methods of `ARTMap`, when input is not sorted by key bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkLoadError {
+  /// Key of input entry at `index` is less than key of previous entry.
+  Unsorted { index: usize },
+  /// Key of input entry at `index` equals key of previous entry or an existing key in the map,
+  /// and duplicate keys are rejected.
+  Duplicate { index: usize },
+}
+
+impl fmt::Display for BulkLoadError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BulkLoadError::Unsorted { index } => write!(f, "input entry {} is out of order", index),
+      BulkLoadError::Duplicate { index } => write!(f, "input entry {} has duplicate key", index),
+    }
+  }
+}
+
+impl Error for BulkLoadError {}
+
+/// Open node on the right-most path.
+struct Frame<K, V> {
+  /// Length of prefix shared by all keys in this node, i.e. the node branches at this byte.
+  depth: usize,
+  leaf: Option<NonNull<LeafNode<K, V>>>,
+  /// Start of children of this node in `BulkBuilder::children`.
+  start: usize,
+}
+
+pub(crate) struct BulkBuilder<K, V, A: Allocator> {
+  /// Open nodes with strictly increasing depth.
+  stack: Vec<Frame<K, V>>,
+  /// Children of open nodes, grouped by node in the same order as `stack`.
+  children: Vec<(u8, BoxedNode<K, V>)>,
+  /// Leaf with maximum key, which is not attached yet.
+  last: Option<NonNull<LeafNode<K, V>>>,
+  last_key: Vec<u8>,
+  length: usize,
+  /// Arena of the map being built, which is taken by the map when building finishes.
+  arena: Option<NodeArena<K, V, A>>,
+}
+
+impl<K, V, A: Allocator> BulkBuilder<K, V, A> {
+  pub(crate) fn new_in(alloc: A) -> Self {
+    Self {
+      stack: Vec::new(),
+      children: Vec::new(),
+      last: None,
+      last_key: Vec::new(),
+      length: 0,
+      arena: Some(NodeArena::new_in(alloc)),
+    }
+  }
+
+  /// Appends an entry, whose encoded key is `key_bytes`. The buffer is swapped with the one
+  /// holding previous key afterwards, so it can be reused.
+  ///
+  /// Returns the entry back with its order against the previous key, if it's not greater.
+  pub(crate) fn push(
+    &mut self,
+    key: K,
+    value: V,
+    key_bytes: &mut Vec<u8>,
+  ) -> Result<(), (Ordering, K, V)> {
+    if self.last.is_some() {
+      match key_bytes.as_slice().cmp(&self.last_key) {
+        Ordering::Greater => {}
+        ord => return Err((ord, key, value)),
+      }
+      let depth = common_len(key_bytes, &self.last_key);
+      self.close(Some(depth));
+    }
+
+    let arena = self.arena.as_mut().unwrap();
+    self.last = Some(arena.alloc(LeafNode::new(key, value)));
+    self.length += 1;
+    mem::swap(&mut self.last_key, key_bytes);
+    Ok(())
+  }
+
+  /// Returns the entry with maximum key.
+  pub(crate) fn last_mut(&mut self) -> Option<(&K, &mut V)> {
+    let last = self.last?;
+    Some(NodeRef::<Mut<'_>, K, V, Leaf>::from_raw_parts(last.cast(), 0).into_kv_mut())
+  }
+
+  pub(crate) fn finish(mut self) -> ARTMap<K, V, A> {
+    self.take_map()
+  }
+
+  /// Moves all entries and the arena into a map. Builder can't be used afterwards.
+  fn take_map(&mut self) -> ARTMap<K, V, A> {
+    ARTMap {
+      root: self.close(None),
+      length: mem::replace(&mut self.length, 0),
+      arena: self.arena.take().unwrap(),
+    }
+  }
+
+  /// Attaches the last leaf, and allocates nodes deeper than `depth` since all their keys have
+  /// been seen. Node at `depth` is left open. When `depth` is `None`, all nodes are allocated and
+  /// the root is returned.
+  fn close(&mut self, depth: Option<usize>) -> Option<BoxedNode<K, V>> {
+    let key = &self.last_key;
+    let mut node: BoxedNode<K, V> = self.last.take()?.cast();
+    let mut is_leaf = true;
+    loop {
+      let parent = match parent_depth(&self.stack, depth) {
+        Some(parent) => parent,
+        None => return Some(node),
+      };
+      if self.stack.last().map(|frame| frame.depth) != Some(parent) {
+        self.stack.push(Frame {
+          depth: parent,
+          leaf: None,
+          start: self.children.len(),
+        });
+      }
+      if is_leaf && key.len() == parent {
+        self.stack.last_mut().unwrap().leaf = Some(node.cast());
+      } else {
+        self.children.push((key[parent], node));
+      }
+      if depth == Some(parent) {
+        return None;
+      }
+
+      let frame = self.stack.pop().unwrap();
+      let partial_key_start = parent_depth(&self.stack, depth).map_or(0, |depth| depth + 1);
+      node = unsafe {
+        new_internal_node(
+          &key[partial_key_start..frame.depth],
+          frame.leaf,
+          self.children.drain(frame.start..),
+          self.arena.as_mut().unwrap(),
+        )
+      };
+      is_leaf = false;
+    }
+  }
+}
+
+/// Returns depth of the node holding next node to attach, which is the top frame unless it's
+/// shallower than `depth`. Returns `None` if that node is the root.
+fn parent_depth<K, V>(stack: &[Frame<K, V>], depth: Option<usize>) -> Option<usize> {
+  match stack.last() {
+    Some(frame) if depth.is_none_or(|depth| frame.depth >= depth) => Some(frame.depth),
+    _ => depth,
+  }
+}
+
+impl<K, V, A: Allocator> Drop for BulkBuilder<K, V, A> {
+  fn drop(&mut self) {
+    if self.arena.is_some() {
+      drop(self.take_map());
+    }
+  }
+}
+
+/// Builds a map from `old` entries and `iter`, which are both sorted by key bytes. When a key in
+/// `iter` equals an existing one, `on_duplicate` is called with the existing entry and the new
+/// value, and returns whether it's resolved. Nodes of the map are allocated from `alloc`.
+///
+/// Building stops at the first entry of `iter` which is out of order or unresolved duplicate, and
+/// the returned map has remaining `old` entries.
+pub(crate) fn bulk_load<K, V, A, I, F>(
+  alloc: A,
+  old: Option<IntoIter<K, V, A>>,
+  iter: I,
+  mut on_duplicate: F,
+) -> (ARTMap<K, V, A>, Result<(), BulkLoadError>)
+where
+  K: ARTKey,
+  A: Allocator,
+  I: IntoIterator<Item = (K, V)>,
+  F: FnMut(&K, &mut V, V) -> bool,
+{
+  fn encode<K: ARTKey>(key: &K, buf: &mut Vec<u8>) {
+    buf.clear();
+    buf.extend_from_slice(key.to_bytes().as_ref());
+  }
+
+  let mut builder = BulkBuilder::new_in(alloc);
+  let mut old = old.into_iter().flatten().peekable();
+  let mut old_key = Vec::new();
+  let mut new_key = Vec::new();
+  if let Some((key, _)) = old.peek() {
+    encode(key, &mut old_key);
+  }
+
+  let mut result = Ok(());
+  for (index, (key, value)) in iter.into_iter().enumerate() {
+    encode(&key, &mut new_key);
+    while old.peek().is_some() && old_key <= new_key {
+      let (key, value) = old.next().unwrap();
+      // Existing keys are sorted and greater than all keys pushed so far.
+      let _ = builder.push(key, value, &mut old_key);
+      if let Some((key, _)) = old.peek() {
+        encode(key, &mut old_key);
+      }
+    }
+
+    match builder.push(key, value, &mut new_key) {
+      Ok(()) => {}
+      Err((Ordering::Equal, _, value)) => {
+        let (key, last_value) = builder.last_mut().unwrap();
+        if !on_duplicate(key, last_value, value) {
+          result = Err(BulkLoadError::Duplicate { index });
+          break;
+        }
+      }
+      Err(_) => {
+        result = Err(BulkLoadError::Unsorted { index });
+        break;
+      }
+    }
+  }
+
+  for (key, value) in old {
+    encode(&key, &mut old_key);
+    let _ = builder.push(key, value, &mut old_key);
+  }
+  (builder.finish(), result)
+}
@@ -0,0 +1,354 @@
+use std::ops::Bound;
+use std::ptr::NonNull;
+
+use allocator_api2::alloc::{Allocator, Global};
+
+use crate::key::ARTKey;
+use crate::map::ARTMap;
+use crate::marker::{Immut, InternalOrLeaf, Leaf, Mut};
+use crate::node::{ChildPos, NodeRef};
+use crate::search::SearchResult;
+use crate::{common_len, DormantMutRef};
+
+/// A cursor over an `ARTMap`.
+///
+/// A `Cursor` is like an iterator, except that it can freely seek back-and-forth.
+///
+/// Cursors always point to an element in the tree, or a "ghost" non-element which is logically
+/// located between the last and first elements of the map.
+pub struct Cursor<'a, K, V> {
+  current: Option<NodeRef<Immut<'a>, K, V, Leaf>>,
+  root: Option<NodeRef<Immut<'a>, K, V, InternalOrLeaf>>,
+}
+
+/// A cursor over an `ARTMap` with editing operations.
+///
+/// Like [`Cursor`], it points to an element or the "ghost" non-element. Elements can be inserted
+/// around current position, or removed at current position.
+pub struct CursorMut<'a, K, V, A: Allocator = Global> {
+  current: Option<NodeRef<Mut<'a>, K, V, Leaf>>,
+  dormant_map: DormantMutRef<'a, ARTMap<K, V, A>>,
+}
+
+impl<'a, K, V> Clone for Cursor<'a, K, V> {
+  fn clone(&self) -> Self {
+    Cursor {
+      current: self.current,
+      root: self.root,
+    }
+  }
+}
+
+impl<'a, K: ARTKey, V> Cursor<'a, K, V> {
+  pub(crate) fn lower_bound<A: Allocator>(map: &'a ARTMap<K, V, A>, bound: Bound<&[u8]>) -> Self {
+    let root = map.root_node_ref();
+    Cursor {
+      current: root.and_then(|root| root.find_lower_bound_node(bound)),
+      root,
+    }
+  }
+
+  pub(crate) fn upper_bound<A: Allocator>(map: &'a ARTMap<K, V, A>, bound: Bound<&[u8]>) -> Self {
+    let root = map.root_node_ref();
+    Cursor {
+      current: root.and_then(|root| root.find_upper_bound_node(bound)),
+      root,
+    }
+  }
+}
+
+impl<'a, K: 'a, V: 'a> Cursor<'a, K, V> {
+  /// Moves the cursor to the next element.
+  ///
+  /// If the cursor is pointing to the "ghost" non-element then this will move it to the first
+  /// element. If it is pointing to the last element then this will move it to the "ghost".
+  pub fn move_next(&mut self) {
+    self.current = self.peek_next_leaf();
+  }
+
+  /// Moves the cursor to the previous element.
+  ///
+  /// If the cursor is pointing to the "ghost" non-element then this will move it to the last
+  /// element. If it is pointing to the first element then this will move it to the "ghost".
+  pub fn move_prev(&mut self) {
+    self.current = self.peek_prev_leaf();
+  }
+
+  /// Returns a reference to the key of the element that the cursor is currently pointing to.
+  pub fn key(&self) -> Option<&'a K> {
+    self.key_value().map(|(k, _)| k)
+  }
+
+  /// Returns a reference to the value of the element that the cursor is currently pointing to.
+  pub fn value(&self) -> Option<&'a V> {
+    self.key_value().map(|(_, v)| v)
+  }
+
+  /// Returns a reference to the key and value of the element that the cursor is currently
+  /// pointing to.
+  pub fn key_value(&self) -> Option<(&'a K, &'a V)> {
+    self.current.map(NodeRef::into_kv_ref)
+  }
+
+  /// Returns a reference to the next element.
+  pub fn peek_next(&self) -> Option<(&'a K, &'a V)> {
+    self.peek_next_leaf().map(NodeRef::into_kv_ref)
+  }
+
+  /// Returns a reference to the previous element.
+  pub fn peek_prev(&self) -> Option<(&'a K, &'a V)> {
+    self.peek_prev_leaf().map(NodeRef::into_kv_ref)
+  }
+
+  fn peek_next_leaf(&self) -> Option<NodeRef<Immut<'a>, K, V, Leaf>> {
+    match self.current {
+      Some(current) => current.next_leaf(),
+      None => self.root.map(NodeRef::minimum_leaf),
+    }
+  }
+
+  fn peek_prev_leaf(&self) -> Option<NodeRef<Immut<'a>, K, V, Leaf>> {
+    match self.current {
+      Some(current) => current.prev_leaf(),
+      None => self.root.map(NodeRef::maximum_leaf),
+    }
+  }
+}
+
+impl<'a, K: ARTKey, V, A: Allocator> CursorMut<'a, K, V, A> {
+  pub(crate) fn lower_bound(map: &'a mut ARTMap<K, V, A>, bound: Bound<&[u8]>) -> Self {
+    let (map, dormant_map) = DormantMutRef::new(map);
+    CursorMut {
+      current: map
+        .root_node_mut()
+        .and_then(|root| root.find_lower_bound_node(bound)),
+      dormant_map,
+    }
+  }
+
+  pub(crate) fn upper_bound(map: &'a mut ARTMap<K, V, A>, bound: Bound<&[u8]>) -> Self {
+    let (map, dormant_map) = DormantMutRef::new(map);
+    CursorMut {
+      current: map
+        .root_node_mut()
+        .and_then(|root| root.find_upper_bound_node(bound)),
+      dormant_map,
+    }
+  }
+
+  /// Inserts a new element into the map after the current one.
+  ///
+  /// If the cursor is pointing at the "ghost" non-element then the new element is inserted at
+  /// the front of the map.
+  ///
+  /// # Panics
+  ///
+  /// This function panics if:
+  /// - the given key compares less than or equal to the current element (if any).
+  /// - the given key compares greater than or equal to the next element (if any).
+  pub fn insert_after(&mut self, key: K, value: V) {
+    if let Some(current) = &self.current {
+      assert!(
+        current.key_bytes().as_ref() < key.to_bytes().as_ref(),
+        "key must be ordered above the current element"
+      );
+    }
+    let next = self.peek_next_leaf();
+    if let Some(next) = &next {
+      assert!(
+        key.to_bytes().as_ref() < next.key_bytes().as_ref(),
+        "key must be ordered below the next element"
+      );
+    }
+    self.insert_unchecked(key, value, next);
+  }
+
+  /// Inserts a new element into the map before the current one.
+  ///
+  /// If the cursor is pointing at the "ghost" non-element then the new element is inserted at
+  /// the end of the map.
+  ///
+  /// # Panics
+  ///
+  /// This function panics if:
+  /// - the given key compares greater than or equal to the current element (if any).
+  /// - the given key compares less than or equal to the previous element (if any).
+  pub fn insert_before(&mut self, key: K, value: V) {
+    if let Some(current) = &self.current {
+      assert!(
+        key.to_bytes().as_ref() < current.key_bytes().as_ref(),
+        "key must be ordered below the current element"
+      );
+    }
+    let prev = self.peek_prev_leaf();
+    if let Some(prev) = &prev {
+      assert!(
+        prev.key_bytes().as_ref() < key.to_bytes().as_ref(),
+        "key must be ordered above the previous element"
+      );
+    }
+    self.insert_unchecked(key, value, prev);
+  }
+
+  /// Inserts `key`, which is ordered between current element and `neighbor`, the element on the
+  /// other side of it. Search starts from the deepest ancestor of either element sharing prefix
+  /// with `key`, instead of root.
+  fn insert_unchecked(
+    &mut self,
+    key: K,
+    value: V,
+    neighbor: Option<NodeRef<Mut<'a>, K, V, Leaf>>,
+  ) {
+    let map = unsafe { self.dormant_map.reborrow() };
+    let near = match (&self.current, neighbor) {
+      (Some(current), _) => NodeRef::from_raw_parts(current.get_inner(), current.prefix_len()),
+      (None, Some(neighbor)) => neighbor,
+      (None, None) => {
+        map.insert(key, value);
+        return;
+      }
+    };
+
+    let common_key_len = common_len(key.to_bytes().as_ref(), near.key_bytes().as_ref());
+    // A leaf in leaf slot holds no other keys, so its parent is the deepest candidate.
+    let mut node = match near.child_pos().map(ChildPos::to_idx) {
+      Some(None) => near.ascend().unwrap().forget_type(),
+      _ => near.forget_type(),
+    };
+    // Path from root to this node only consumes bytes shared by both keys.
+    while node.prefix_len() > common_key_len {
+      node = node.ascend().unwrap().forget_type();
+    }
+    let node = match node.search_tree(key.to_bytes().as_ref()) {
+      SearchResult::NotFound(node) => node,
+      _ => unreachable!(),
+    };
+    let split = node.get_inner();
+    unsafe { node.insert_node(key, value, NonNull::from(&mut map.root), &mut map.arena) };
+    map.length += 1;
+
+    // Current leaf is moved under a new parent when the key diverges right at it, and the new
+    // parent consumes bytes up to the divergence.
+    if let Some(current) = self.current.as_mut().filter(|c| c.get_inner() == split) {
+      let edge_len = (current.key_bytes().as_ref().len() > common_key_len) as usize;
+      *current = NodeRef::from_raw_parts(current.get_inner(), common_key_len + edge_len);
+    }
+  }
+
+  /// Removes the current element from the map.
+  ///
+  /// The element that was removed is returned, and the cursor is moved to point to the next
+  /// element. If the cursor is currently pointing to the "ghost" non-element then no element is
+  /// removed and `None` is returned.
+  pub fn remove_current(&mut self) -> Option<(K, V)> {
+    let current = self.current.take()?;
+    let next = current.next_leaf().map(|next| next.get_inner());
+    let kv = self.remove_leaf(current);
+    self.current = next.map(NodeRef::from_node_ptr);
+    Some(kv)
+  }
+
+  /// Removes the current element from the map.
+  ///
+  /// The element that was removed is returned, and the cursor is moved to point to the previous
+  /// element. If the cursor is currently pointing to the "ghost" non-element then no element is
+  /// removed and `None` is returned.
+  pub fn remove_current_and_move_back(&mut self) -> Option<(K, V)> {
+    let current = self.current.take()?;
+    let prev = current.prev_leaf().map(|prev| prev.get_inner());
+    let kv = self.remove_leaf(current);
+    self.current = prev.map(NodeRef::from_node_ptr);
+    Some(kv)
+  }
+
+  fn remove_leaf(&mut self, leaf: NodeRef<Mut<'a>, K, V, Leaf>) -> (K, V) {
+    let map = unsafe { self.dormant_map.reborrow() };
+    map.length -= 1;
+    unsafe { leaf.remove_leaf(NonNull::from(&mut map.root), &mut map.arena) }
+  }
+}
+
+impl<'a, K: 'a, V: 'a, A: Allocator> CursorMut<'a, K, V, A> {
+  /// Moves the cursor to the next element.
+  ///
+  /// If the cursor is pointing to the "ghost" non-element then this will move it to the first
+  /// element. If it is pointing to the last element then this will move it to the "ghost".
+  pub fn move_next(&mut self) {
+    self.current = self.peek_next_leaf();
+  }
+
+  /// Moves the cursor to the previous element.
+  ///
+  /// If the cursor is pointing to the "ghost" non-element then this will move it to the last
+  /// element. If it is pointing to the first element then this will move it to the "ghost".
+  pub fn move_prev(&mut self) {
+    self.current = self.peek_prev_leaf();
+  }
+
+  /// Returns a reference to the key of the element that the cursor is currently pointing to.
+  pub fn key(&self) -> Option<&K> {
+    self.current.as_ref().map(|c| c.as_leaf_ref().key_ref())
+  }
+
+  /// Returns a reference to the value of the element that the cursor is currently pointing to.
+  pub fn value(&self) -> Option<&V> {
+    self.current.as_ref().map(|c| c.as_leaf_ref().value_ref())
+  }
+
+  /// Returns a mutable reference to the value of the element that the cursor is currently
+  /// pointing to.
+  pub fn value_mut(&mut self) -> Option<&mut V> {
+    self.current.as_mut().map(|c| c.as_leaf_mut().value_mut())
+  }
+
+  /// Returns a reference to the key and value of the element that the cursor is currently
+  /// pointing to.
+  pub fn key_value(&self) -> Option<(&K, &V)> {
+    self
+      .current
+      .as_ref()
+      .map(|c| (c.as_leaf_ref().key_ref(), c.as_leaf_ref().value_ref()))
+  }
+
+  /// Returns a reference to the key and a mutable reference to the value of the element that the
+  /// cursor is currently pointing to.
+  pub fn key_value_mut(&mut self) -> Option<(&K, &mut V)> {
+    self.current.as_mut().map(NodeRef::kv_mut)
+  }
+
+  /// Returns a reference to the next element.
+  pub fn peek_next(&mut self) -> Option<(&K, &mut V)> {
+    self.peek_next_leaf().map(NodeRef::into_kv_mut)
+  }
+
+  /// Returns a reference to the previous element.
+  pub fn peek_prev(&mut self) -> Option<(&K, &mut V)> {
+    self.peek_prev_leaf().map(NodeRef::into_kv_mut)
+  }
+
+  /// Returns a read-only cursor pointing to the current element.
+  pub fn as_cursor(&self) -> Cursor<'_, K, V> {
+    Cursor {
+      current: self.current.as_ref().map(NodeRef::reborrow),
+      root: unsafe { self.dormant_map.reborrow_shared() }.root_node_ref(),
+    }
+  }
+
+  fn peek_next_leaf(&mut self) -> Option<NodeRef<Mut<'a>, K, V, Leaf>> {
+    match &self.current {
+      Some(current) => current.next_leaf(),
+      None => unsafe { self.dormant_map.reborrow() }
+        .root_node_mut()
+        .map(NodeRef::minimum_leaf),
+    }
+  }
+
+  fn peek_prev_leaf(&mut self) -> Option<NodeRef<Mut<'a>, K, V, Leaf>> {
+    match &self.current {
+      Some(current) => current.prev_leaf(),
+      None => unsafe { self.dormant_map.reborrow() }
+        .root_node_mut()
+        .map(NodeRef::maximum_leaf),
+    }
+  }
+}
@@ -1,43 +1,63 @@
-use either::Either;
 use std::mem;
 use std::ptr::NonNull;
 
+use allocator_api2::alloc::{Allocator, Global};
+
 use crate::entry::Entry::{Occupied, Vacant};
 
+use crate::key::ARTKey;
+use crate::map::ARTMap;
 use crate::marker::{InternalOrLeaf, Leaf, Mut};
-use crate::node::{Handle, LeafNode, NodeRef};
+use crate::node::{LeafNode, NodeRef};
+use crate::DormantMutRef;
 
 
-pub enum Entry<'a, K, V> {
-  Vacant(VacantEntry<'a, K, V>),
-  Occupied(OccupiedEntry<'a, K, V>),
+pub enum Entry<'a, K, V, A: Allocator = Global> {
+  Vacant(VacantEntry<'a, K, V, A>),
+  Occupied(OccupiedEntry<'a, K, V, A>),
 }
 
-pub struct VacantEntry<'a, K, V> {
+pub struct VacantEntry<'a, K, V, A: Allocator = Global> {
   pub(crate) key: K,
-  /// When map is empty, it should be a pointer to new node.
-  /// When the map is not empty, it's a node ref.
-  pub(crate) node: Either<Handle<K, V>, NodeRef<Mut<'a>, K, V, InternalOrLeaf>>,
+  /// Node where search stopped. It's `None` when map is empty.
+  pub(crate) node: Option<NodeRef<Mut<'a>, K, V, InternalOrLeaf>>,
+  pub(crate) dormant_map: DormantMutRef<'a, ARTMap<K, V, A>>,
 }
 
-pub struct OccupiedEntry<'a, K, V> {
+pub struct OccupiedEntry<'a, K, V, A: Allocator = Global> {
+  /// Key used to look up this entry. It's `None` when this entry is not created by `entry`.
+  pub(crate) key: Option<K>,
   pub(crate) node: NodeRef<Mut<'a>, K, V, Leaf>,
+  pub(crate) dormant_map: DormantMutRef<'a, ARTMap<K, V, A>>,
 }
 
-impl<'a, K, V> Entry<'a, K, V> {
+impl<'a, K, V, A: Allocator> Entry<'a, K, V, A> {
   pub(crate) fn new_vacant(
     key: K,
-    node: Either<Handle<K, V>, NodeRef<Mut<'a>, K, V, InternalOrLeaf>>,
+    node: Option<NodeRef<Mut<'a>, K, V, InternalOrLeaf>>,
+    dormant_map: DormantMutRef<'a, ARTMap<K, V, A>>,
   ) -> Self {
-    Entry::Vacant(VacantEntry { key, node })
+    Entry::Vacant(VacantEntry {
+      key,
+      node,
+      dormant_map,
+    })
   }
 
-  pub(crate) fn new_occupied(node: NodeRef<Mut<'a>, K, V, Leaf>) -> Self {
-    Entry::Occupied(OccupiedEntry { node })
+  pub(crate) fn new_occupied(
+    key: K,
+    node: NodeRef<Mut<'a>, K, V, Leaf>,
+    dormant_map: DormantMutRef<'a, ARTMap<K, V, A>>,
+  ) -> Self {
+    Entry::Occupied(OccupiedEntry {
+      key: Some(key),
+      node,
+      dormant_map,
+    })
   }
 }
 
-impl<'a, K: AsRef<[u8]> + 'a, V: 'a> Entry<'a, K, V> {
+impl<'a, K: ARTKey + 'a, V: 'a, A: Allocator> Entry<'a, K, V, A> {
   pub fn or_insert(self, value: V) -> &'a mut V {
     match self {
       Entry::Occupied(entry) => entry.into_mut(),
@@ -64,8 +84,8 @@ impl<'a, K: AsRef<[u8]> + 'a, V: 'a> Entry<'a, K, V> {
 
   pub fn key(&self) -> &K {
     match self {
-      Occupied(ref entry) => &entry.key(),
-      Vacant(ref entry) => &entry.key(),
+      Occupied(ref entry) => entry.key(),
+      Vacant(ref entry) => entry.key(),
     }
   }
 
@@ -78,15 +98,29 @@ impl<'a, K: AsRef<[u8]> + 'a, V: 'a> Entry<'a, K, V> {
       Entry::Vacant(e) => Entry::Vacant(e),
     }
   }
+
+  /// Sets the value of the entry, and returns an `OccupiedEntry`.
+  pub fn insert_entry(self, value: V) -> OccupiedEntry<'a, K, V, A> {
+    match self {
+      Entry::Occupied(mut entry) => {
+        entry.insert(value);
+        entry
+      }
+      Entry::Vacant(entry) => entry.insert_entry(value),
+    }
+  }
 }
 
-impl<'a, K: AsRef<u8>, V: Default> Entry<'a, K, V> {
+impl<'a, K: ARTKey + 'a, V: Default + 'a, A: Allocator> Entry<'a, K, V, A> {
   pub fn or_default(self) -> &'a mut V {
-    todo!()
+    match self {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => entry.insert(Default::default()),
+    }
   }
 }
 
-impl<'a, K: AsRef<[u8]> + 'a, V: 'a> VacantEntry<'a, K, V> {
+impl<'a, K: ARTKey + 'a, V: 'a, A: Allocator> VacantEntry<'a, K, V, A> {
   pub fn key(&self) -> &K {
     &self.key
   }
@@ -96,25 +130,53 @@ impl<'a, K: AsRef<[u8]> + 'a, V: 'a> VacantEntry<'a, K, V> {
   }
 
   pub fn insert(self, value: V) -> &'a mut V {
-    match self.node {
-      Either::Left(mut handle) => {
-        let new_leaf = LeafNode::new_root(self.key, value);
-        let mut leaf_node = NonNull::from(Box::leak(new_leaf));
-        unsafe {
-          std::ptr::write(handle.as_mut(), Some(leaf_node.cast()));
-          leaf_node.as_mut().value_mut()
-        }
-      }
-      Either::Right(node) => {
-        unsafe {
-          node.insert_node(self.key, value).as_mut()
-        }
-      }
+    let VacantEntry {
+      key,
+      node,
+      mut dormant_map,
+    } = self;
+    let mut leaf = Self::insert_leaf(key, value, node, &mut dormant_map);
+    unsafe { leaf.as_mut().value_mut() }
+  }
+
+  /// Sets the value of the entry with the `VacantEntry`'s key, and returns an `OccupiedEntry`.
+  pub fn insert_entry(self, value: V) -> OccupiedEntry<'a, K, V, A> {
+    let VacantEntry {
+      key,
+      node,
+      mut dormant_map,
+    } = self;
+    let leaf = Self::insert_leaf(key, value, node, &mut dormant_map);
+    OccupiedEntry {
+      key: None,
+      node: NodeRef::from_node_ptr(leaf.cast()),
+      dormant_map,
     }
   }
+
+  fn insert_leaf(
+    key: K,
+    value: V,
+    node: Option<NodeRef<Mut<'a>, K, V, InternalOrLeaf>>,
+    dormant_map: &mut DormantMutRef<'a, ARTMap<K, V, A>>,
+  ) -> NonNull<LeafNode<K, V>> {
+    let map = unsafe { dormant_map.reborrow() };
+    let root = NonNull::from(&mut map.root);
+    let new_leaf = match node {
+      None => {
+        let new_leaf = map.arena.alloc(LeafNode::new(key, value));
+        map.root = Some(new_leaf.cast());
+        new_leaf
+      }
+      Some(node) => unsafe { node.insert_node(key, value, root, &mut map.arena) },
+    };
+    // Counted only once the leaf is linked, in case encoding the key panics.
+    map.length += 1;
+    new_leaf
+  }
 }
 
-impl<'a, K: AsRef<[u8]>, V> OccupiedEntry<'a, K, V> {
+impl<'a, K: 'a, V: 'a, A: Allocator> OccupiedEntry<'a, K, V, A> {
   pub fn key(&self) -> &K {
     self.node.as_leaf_ref().key_ref()
   }
@@ -135,11 +197,38 @@ impl<'a, K: AsRef<[u8]>, V> OccupiedEntry<'a, K, V> {
     mem::replace(self.get_mut(), value)
   }
 
-  pub fn remove_kv(mut self) -> (K, V) {
-    // TODO: This is not enough, parent should change
+  /// Takes the value of the entry out of the map, and returns it.
+  pub fn remove(self) -> V {
+    self.remove_entry().1
+  }
+
+  /// Takes the key-value pair out of the map, and returns it.
+  pub fn remove_entry(self) -> (K, V) {
+    let map = unsafe { self.dormant_map.awaken() };
+    map.length -= 1;
     unsafe {
-      self.node.replace_self_in_parent(None);
+      self
+        .node
+        .remove_leaf(NonNull::from(&mut map.root), &mut map.arena)
     }
-    self.node.into_kv()
+  }
+
+  /// Same as [`remove_entry`](Self::remove_entry).
+  pub fn remove_kv(self) -> (K, V) {
+    self.remove_entry()
+  }
+
+  /// Replaces the key stored in the map with the key used to create this entry, and returns the
+  /// old key. Both keys have same bytes, so the tree is left untouched.
+  ///
+  /// # Panics
+  ///
+  /// Panics if this entry was not created through [`ARTMap::entry`].
+  pub fn replace_key(mut self) -> K {
+    let key = self
+      .key
+      .take()
+      .expect("replace_key called on an entry not created by `ARTMap::entry`");
+    mem::replace(self.node.as_leaf_mut().key_mut(), key)
   }
 }
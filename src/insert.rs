@@ -1,13 +1,13 @@
 use std::ptr::NonNull;
-use std::slice::from_raw_parts;
-
 
+use allocator_api2::alloc::Allocator;
 
 use crate::common_len;
+use crate::key::ARTKey;
 use crate::marker::{Internal, InternalOrLeaf, Leaf, Mut};
-use crate::node::{InternalNode4, LeafNode, NodeImpl, NodeRef};
+use crate::node::{Handle, InternalNode4, LeafNode, NodeArena, NodeImpl, NodeRef, PartialKey};
 
-impl<'a, K: AsRef<[u8]>, V> NodeRef<Mut<'a>, K, V, InternalOrLeaf> {
+impl<'a, K: ARTKey, V> NodeRef<Mut<'a>, K, V, InternalOrLeaf> {
   /// Insert `key`, `value` into this node.
   ///
   /// This method is designed to be used by entry api, which already checked prefix against parents
@@ -15,167 +15,152 @@ impl<'a, K: AsRef<[u8]>, V> NodeRef<Mut<'a>, K, V, InternalOrLeaf> {
   ///
   /// # Returns
   ///
-  /// A new subtree's root node with value inserted.
+  /// Pointer to the leaf inserted.
   ///
   /// # Panics
   ///
   /// If same key already exists.
-  pub(crate) fn insert_node(self, key: K, value: V) -> NonNull<V> {
+  ///
+  /// # Safety
+  ///
+  /// `root` must point to the root of the tree containing this node, and `arena` must be the one
+  /// its nodes are allocated from.
+  pub(crate) unsafe fn insert_node<A: Allocator>(
+    self,
+    key: K,
+    value: V,
+    root: Handle<K, V>,
+    arena: &mut NodeArena<K, V, A>,
+  ) -> NonNull<LeafNode<K, V>> {
     match self.downcast() {
-      NodeImpl::Internal(internal) => internal.insert_node(key, value),
-      NodeImpl::Leaf(leaf) => leaf.insert_node(key, value)
+      NodeImpl::Internal(internal) => internal.insert_node(key, value, root, arena),
+      NodeImpl::Leaf(leaf) => leaf.insert_node(key, value, root, arena),
     }
   }
 }
 
-impl<'a, K: 'a + AsRef<[u8]>, V: 'a> NodeRef<Mut<'a>, K, V, Internal> {
+impl<'a, K: 'a + ARTKey, V: 'a> NodeRef<Mut<'a>, K, V, Internal> {
   /// Insert into current node.
   ///
-  /// # Returns
-  ///
-  /// When current node doesn't need to split to new node, just return itself.
-  /// Otherwise, returns a newly created node.
-  fn insert_node(
+  /// When the input key diverges inside partial key of this node, a new parent is created at the
+  /// divergence point. When the input key needs a new child and this node is full, this node is
+  /// replaced with a larger one.
+  unsafe fn insert_node<A: Allocator>(
     mut self,
     key: K,
     value: V,
-  ) -> NonNull<V> {
-    let this_partial_key = self.partial_key();
-    let input_partial_key = &key.as_ref()[self.prefix_len()..];
-
-    let common_key_len = common_len(this_partial_key, input_partial_key);
-
-    // Insert self
-    if common_key_len < this_partial_key.len() {
-      let mut new_parent = unsafe { InternalNode4::new() };
-      new_parent
+    root: Handle<K, V>,
+    arena: &mut NodeArena<K, V, A>,
+  ) -> NonNull<LeafNode<K, V>> {
+    // Full partial key may be borrowed from a leaf below, so partial keys after split are computed
+    // up front.
+    let (new_k, split) = self.with_partial_key(|this_partial_key| {
+      let input_key = key.to_bytes();
+      let input_partial_key = &input_key.as_ref()[self.prefix_len()..];
+      let common_key_len = common_len(this_partial_key, input_partial_key);
+      let split = (common_key_len < this_partial_key.len()).then(|| {
+        let mut new_parent = arena.alloc(InternalNode4::new());
+        new_parent
+          .as_mut()
           .base_mut()
           .set_partial_key(&this_partial_key[0..common_key_len]);
-      let _new_parent_prefix_len = self.prefix_len();
-
-      // First create leaf node using new value
-      let leaf_value_ptr = if common_key_len < input_partial_key.len() {
-        let new_k = input_partial_key[common_key_len];
-        let mut new_leaf_node = LeafNode::new(key, value);
-        let leaf_value_ptr = NonNull::from(new_leaf_node.value_mut());
-        if let Some(_) =
-        unsafe { new_parent.set_child(new_k, NonNull::from(Box::leak(new_leaf_node)).cast()) }
-        {
-          unreachable!("This should not happen!");
+        (
+          new_parent,
+          this_partial_key[common_key_len],
+          PartialKey::new(&this_partial_key[common_key_len + 1..]),
+        )
+      });
+      (input_partial_key.get(common_key_len).copied(), split)
+    });
+
+    let new_leaf_ptr = arena.alloc(LeafNode::new(key, value));
+
+    if let Some((new_parent, this_k, this_partial_key)) = split {
+      // First insert leaf node using new value
+      match new_k {
+        Some(new_k) => {
+          InternalNode4::set_child(new_parent, new_k, new_leaf_ptr.cast());
         }
-
-        leaf_value_ptr
-      } else {
-        let mut new_leaf_node = LeafNode::new(key, value);
-        let leaf_value_ptr = NonNull::from(new_leaf_node.value_mut());
-        unsafe {
-          new_parent
-              .base_mut()
-              .set_leaf(NonNull::from(Box::leak(new_leaf_node)));
-        }
-        leaf_value_ptr
-      };
-
-      // Insert self as child to new parent
-      {
-        let new_k = this_partial_key[common_key_len];
-        let new_partial_key: &[u8] = if (common_key_len + 1) >= this_partial_key.len() {
-          &[]
-        } else {
-          let slice = &this_partial_key[(common_key_len + 1)..];
-          // SAFETY: The copy will not override.
-          unsafe { from_raw_parts(slice.as_ptr(), slice.len()) }
-        };
-
-        self.set_partial_key(new_partial_key);
-
-        if let Some(_) = unsafe { new_parent.set_child(new_k, self.get_inner()) } {
-          unreachable!("This should not happen!");
-        }
-
-        unsafe {
-          self.replace_self_in_parent(Some(NonNull::from(Box::leak(new_parent)).cast()));
+        None => {
+          InternalNode4::set_leaf(new_parent, new_leaf_ptr);
         }
       }
 
-      leaf_value_ptr
+      // Insert self as child to new parent
+      self.replace_self_in_parent(Some(new_parent.cast()), root);
+      self.set_partial_key(this_partial_key);
+      InternalNode4::set_child(new_parent, this_k, self.get_inner());
     } else {
-      if common_key_len < input_partial_key.len() {
-        let new_k = input_partial_key[common_key_len];
-        let mut new_leaf_node = LeafNode::new(key, value);
-        let leaf_value_ptr = NonNull::from(new_leaf_node.value_mut());
-        if let Some(_) =
-        unsafe { self.insert_child(new_k, NonNull::from(Box::leak(new_leaf_node)).cast()) }
-        {
-          unreachable!()
-        }
-        leaf_value_ptr
-      } else {
-        let mut new_leaf_node = LeafNode::new(key, value);
-        let leaf_value_ptr = NonNull::from(new_leaf_node.value_mut());
-        if let Some(_) = unsafe { self.set_leaf(NonNull::from(Box::leak(new_leaf_node))) } {
-          unreachable!()
-        }
-        leaf_value_ptr
+      let prev = match new_k {
+        Some(new_k) => self.insert_child(new_k, new_leaf_ptr.cast(), root, arena),
+        None => self.set_leaf(new_leaf_ptr).map(NonNull::cast),
+      };
+      if prev.is_some() {
+        unreachable!("This should not happen!");
       }
     }
+
+    new_leaf_ptr
   }
 }
 
-impl<'a, K: 'a + AsRef<[u8]>, V: 'a> NodeRef<Mut<'a>, K, V, Leaf> {
-  fn insert_node(mut self, key: K, value: V) -> NonNull<V> {
-    let this_partial_key = self.partial_key();
-    let input_partial_key = &key.as_ref()[self.partial_key().len()..];
-
-    let common_key_len = common_len(this_partial_key, input_partial_key);
-
-    let mut new_parent = unsafe { InternalNode4::new() };
-    new_parent
+impl<'a, K: 'a + ARTKey, V: 'a> NodeRef<Mut<'a>, K, V, Leaf> {
+  /// Insert into current node by replacing it with a new parent containing both leaves.
+  unsafe fn insert_node<A: Allocator>(
+    mut self,
+    key: K,
+    value: V,
+    root: Handle<K, V>,
+    arena: &mut NodeArena<K, V, A>,
+  ) -> NonNull<LeafNode<K, V>> {
+    let mut new_parent = InternalNode4::new();
+    let (new_k, this_k) = {
+      let this_key = self.key_bytes();
+      let this_partial_key = &this_key.as_ref()[self.prefix_len()..];
+      let input_key = key.to_bytes();
+      let input_partial_key = &input_key.as_ref()[self.prefix_len()..];
+
+      let common_key_len = common_len(this_partial_key, input_partial_key);
+      new_parent
         .base_mut()
         .set_partial_key(&this_partial_key[0..common_key_len]);
+      (
+        input_partial_key.get(common_key_len).copied(),
+        this_partial_key.get(common_key_len).copied(),
+      )
+    };
+
+    // Equal keys would both take the leaf slot of the new parent.
+    assert!(
+      new_k.is_some() || this_k.is_some(),
+      "Key already exists in the tree!"
+    );
+
+    // Nodes are allocated only after keys are encoded, which may panic.
+    let new_parent_ptr = arena.alloc(new_parent);
+    let new_leaf_ptr = arena.alloc(LeafNode::new(key, value));
 
     // Insert new leaf node
-    let leaf_value_ptr = {
-      if common_key_len < input_partial_key.len() {
-        let new_k = input_partial_key[common_key_len];
-        let _new_prefix_len = self.prefix_len() + common_key_len + 1;
-        let mut new_leaf = LeafNode::new(key, value);
-        let leaf_value_ptr = NonNull::from(new_leaf.value_mut());
-        unsafe {
-          new_parent.set_child(new_k, NonNull::from(Box::leak(new_leaf)).cast());
-        }
-        leaf_value_ptr
-      } else {
-        let mut new_leaf = LeafNode::new(key, value);
-        let leaf_value_ptr = NonNull::from(new_leaf.value_mut());
-        unsafe {
-          new_parent
-              .base_mut()
-              .set_leaf(NonNull::from(Box::leak(new_leaf)));
-        }
-        leaf_value_ptr
+    match new_k {
+      Some(new_k) => {
+        InternalNode4::set_child(new_parent_ptr, new_k, new_leaf_ptr.cast());
       }
-    };
+      None => {
+        InternalNode4::set_leaf(new_parent_ptr, new_leaf_ptr);
+      }
+    }
 
     // Insert current node
-    {
-      if common_key_len < this_partial_key.len() {
-        let new_k = this_partial_key[common_key_len];
-        self.set_prefix_len(self.prefix_len() + common_key_len + 1);
-        unsafe {
-          new_parent.set_child(new_k, self.get_inner());
-        }
-      } else {
-        self.set_prefix_len(self.prefix_len() + common_key_len);
-        unsafe {
-          new_parent
-              .base_mut()
-              .set_leaf(self.get_inner().cast());
-          self.replace_self_in_parent(Some(NonNull::from(Box::leak(new_parent)).cast()));
-        }
+    self.replace_self_in_parent(Some(new_parent_ptr.cast()), root);
+    match this_k {
+      Some(this_k) => {
+        InternalNode4::set_child(new_parent_ptr, this_k, self.get_inner());
+      }
+      None => {
+        InternalNode4::set_leaf(new_parent_ptr, self.get_inner().cast());
       }
     }
 
-    leaf_value_ptr
+    new_leaf_ptr
   }
 }
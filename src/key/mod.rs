@@ -0,0 +1,287 @@
+//! Order-preserving encoding of keys into bytes.
+//!
+//! `ARTMap` orders entries by the bytes returned from [`ARTKey::to_bytes`], so an encoding must
+//! make byte order equal to natural order of keys. Composite keys are supported for tuples of
+//! [`KeyComponent`]s.
+//!
+//! Keys used to be any `K: AsRef<[u8]>`, ordered by their raw bytes. Byte strings, strings and
+//! their `&`, `Box`, `Rc`, `Arc` and `Cow` wrappers are still encoded as raw bytes, but other
+//! `AsRef<[u8]>` types need an [`ARTKey`] impl of their own.
+
+use std::borrow::{Cow, ToOwned};
+use std::ops::Bound;
+use std::rc::Rc;
+use std::sync::Arc;
+
+pub use tuple::{decode_key, KeyComponent, KeyReader};
+
+mod tuple;
+
+/// Key which can be stored in `ARTMap`.
+///
+/// Implementations must guarantee that for any two keys `a` and `b`, `a.cmp(b)` equals
+/// `a.to_bytes().as_ref().cmp(b.to_bytes().as_ref())`.
+pub trait ARTKey {
+  /// Encoded bytes of the key.
+  type Bytes<'a>: AsRef<[u8]>
+  where
+    Self: 'a;
+
+  /// Encodes this key into bytes.
+  fn to_bytes(&self) -> Self::Bytes<'_>;
+}
+
+/// Key whose encoding has the same length for all values, so arrays of it are ordered by
+/// concatenating encodings of elements.
+pub trait FixedWidthKey: ARTKey + Sized {
+  /// Returns encoding of `keys` without copying, if memory of keys is their encoding.
+  fn as_encoded(keys: &[Self]) -> Option<&[u8]> {
+    let _ = keys;
+    None
+  }
+}
+
+macro_rules! impl_unsigned {
+  ($($t:ty),*) => {
+    $(
+      impl ARTKey for $t {
+        type Bytes<'a> = [u8; std::mem::size_of::<$t>()];
+
+        fn to_bytes(&self) -> Self::Bytes<'_> {
+          self.to_be_bytes()
+        }
+      }
+
+      impl FixedWidthKey for $t {}
+    )*
+  };
+}
+
+macro_rules! impl_signed {
+  ($($t:ty => $u:ty),*) => {
+    $(
+      impl ARTKey for $t {
+        type Bytes<'a> = [u8; std::mem::size_of::<$t>()];
+
+        /// Flips the sign bit so that negative numbers come first.
+        fn to_bytes(&self) -> Self::Bytes<'_> {
+          ((*self as $u) ^ (1 << (<$u>::BITS - 1))).to_be_bytes()
+        }
+      }
+
+      impl FixedWidthKey for $t {}
+    )*
+  };
+}
+
+macro_rules! impl_float {
+  ($($t:ty => $u:ty),*) => {
+    $(
+      impl ARTKey for $t {
+        type Bytes<'a> = [u8; std::mem::size_of::<$t>()];
+
+        /// Uses the IEEE 754 total order: negative numbers have all bits flipped, positive
+        /// numbers have only the sign bit flipped. Note that `-0.0` is ordered before `0.0`, and
+        /// NaNs are ordered by their sign and payload.
+        fn to_bytes(&self) -> Self::Bytes<'_> {
+          let bits = self.to_bits();
+          let sign = 1 << (<$u>::BITS - 1);
+          let bits = if bits & sign != 0 { !bits } else { bits ^ sign };
+          bits.to_be_bytes()
+        }
+      }
+
+      impl FixedWidthKey for $t {}
+    )*
+  };
+}
+
+impl_unsigned!(u16, u32, u64, u128, usize);
+impl_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+impl_float!(f32 => u32, f64 => u64);
+
+impl ARTKey for u8 {
+  type Bytes<'a> = [u8; 1];
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    [*self]
+  }
+}
+
+impl FixedWidthKey for u8 {
+  fn as_encoded(keys: &[Self]) -> Option<&[u8]> {
+    Some(keys)
+  }
+}
+
+impl ARTKey for bool {
+  type Bytes<'a> = [u8; 1];
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    [*self as u8]
+  }
+}
+
+impl FixedWidthKey for bool {}
+
+impl ARTKey for char {
+  type Bytes<'a> = [u8; 4];
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    (*self as u32).to_be_bytes()
+  }
+}
+
+impl FixedWidthKey for char {}
+
+impl ARTKey for [u8] {
+  type Bytes<'a> = &'a [u8];
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    self
+  }
+}
+
+impl<T: FixedWidthKey, const N: usize> ARTKey for [T; N] {
+  type Bytes<'a>
+    = Cow<'a, [u8]>
+  where
+    T: 'a;
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    match T::as_encoded(self) {
+      Some(bytes) => Cow::Borrowed(bytes),
+      None => {
+        let mut out = Vec::new();
+        for k in self {
+          out.extend_from_slice(k.to_bytes().as_ref());
+        }
+        Cow::Owned(out)
+      }
+    }
+  }
+}
+
+impl<T: FixedWidthKey, const N: usize> FixedWidthKey for [T; N] {}
+
+impl ARTKey for Vec<u8> {
+  type Bytes<'a> = &'a [u8];
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    self
+  }
+}
+
+impl ARTKey for str {
+  type Bytes<'a> = &'a [u8];
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    self.as_bytes()
+  }
+}
+
+impl ARTKey for String {
+  type Bytes<'a> = &'a [u8];
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    self.as_bytes()
+  }
+}
+
+impl<T: ARTKey + ?Sized> ARTKey for &T {
+  type Bytes<'a>
+    = T::Bytes<'a>
+  where
+    Self: 'a;
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    (**self).to_bytes()
+  }
+}
+
+impl<T: ARTKey + ?Sized> ARTKey for Box<T> {
+  type Bytes<'a>
+    = T::Bytes<'a>
+  where
+    Self: 'a;
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    (**self).to_bytes()
+  }
+}
+
+impl<T: ARTKey + ?Sized> ARTKey for Rc<T> {
+  type Bytes<'a>
+    = T::Bytes<'a>
+  where
+    Self: 'a;
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    (**self).to_bytes()
+  }
+}
+
+impl<T: ARTKey + ?Sized> ARTKey for Arc<T> {
+  type Bytes<'a>
+    = T::Bytes<'a>
+  where
+    Self: 'a;
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    (**self).to_bytes()
+  }
+}
+
+impl<T: ARTKey + ToOwned + ?Sized> ARTKey for Cow<'_, T> {
+  type Bytes<'a>
+    = T::Bytes<'a>
+  where
+    Self: 'a;
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    (**self).to_bytes()
+  }
+}
+
+/// Key type which can be looked up by a borrowed form `Q`, in the spirit of
+/// [`Borrow`](std::borrow::Borrow).
+///
+/// Implementations must guarantee that `Q` encodes to the same bytes as the key it's borrowed
+/// from.
+pub trait ARTBorrow<Q: ?Sized> {}
+
+impl<T: ?Sized> ARTBorrow<T> for T {}
+
+impl<T: ?Sized> ARTBorrow<T> for &T {}
+
+impl<T: ?Sized> ARTBorrow<T> for Box<T> {}
+
+impl<T: ?Sized> ARTBorrow<T> for Rc<T> {}
+
+impl<T: ?Sized> ARTBorrow<T> for Arc<T> {}
+
+impl<T: ToOwned + ?Sized> ARTBorrow<T> for Cow<'_, T> {}
+
+impl ARTBorrow<[u8]> for str {}
+
+impl ARTBorrow<str> for String {}
+
+impl ARTBorrow<[u8]> for String {}
+
+impl<const N: usize> ARTBorrow<[u8; N]> for String {}
+
+impl ARTBorrow<[u8]> for Vec<u8> {}
+
+impl<const N: usize> ARTBorrow<[u8; N]> for Vec<u8> {}
+
+impl<const N: usize> ARTBorrow<[u8]> for [u8; N] {}
+
+/// Encodes key in `bound`.
+pub(crate) fn encode_bound<Q: ARTKey + ?Sized>(bound: Bound<&Q>) -> Bound<Q::Bytes<'_>> {
+  bound.map(ARTKey::to_bytes)
+}
+
+/// Views encoded bound as bytes.
+pub(crate) fn bytes_bound<B: AsRef<[u8]>>(bound: &Bound<B>) -> Bound<&[u8]> {
+  bound.as_ref().map(AsRef::as_ref)
+}
@@ -0,0 +1,255 @@
+//! Order-preserving encoding of composite keys.
+//!
+//! Each component of a tuple is encoded so that it's self-delimiting, thus keys are ordered by
+//! their first component, then by the second one, and so on. Fixed-size components are written
+//! as is. Variable-length components escape `0x00` as `0x00 0xFF` and are terminated by
+//! `0x00 0x00`, so a shorter string sorts before its extensions. Components wrapped in
+//! [`Reverse`] are ordered descending by inverting all their bytes.
+
+use std::cmp::Reverse;
+
+use crate::key::ARTKey;
+
+/// Component of a composite key.
+pub trait KeyComponent: Sized {
+  /// Appends self-delimiting encoding of this component to `out`.
+  fn encode(&self, out: &mut Vec<u8>);
+
+  /// Decodes a component from `reader`. Returns `None` if the input is malformed.
+  fn decode(reader: &mut KeyReader<'_>) -> Option<Self>;
+
+  /// Decodes this component used as a whole key, i.e. from the bytes of [`ARTKey::to_bytes`].
+  /// Returns `None` if `bytes` is malformed or has trailing bytes.
+  ///
+  /// Defaults to decoding the self-delimiting encoding, which keys of most components share.
+  fn decode_key(bytes: &[u8]) -> Option<Self> {
+    let mut reader = KeyReader::new(bytes);
+    let key = Self::decode(&mut reader)?;
+    if reader.is_empty() {
+      Some(key)
+    } else {
+      None
+    }
+  }
+}
+
+/// Reader over encoded key bytes, used to decode [`KeyComponent`]s.
+pub struct KeyReader<'a> {
+  bytes: &'a [u8],
+  /// Whether bytes are inverted, i.e. reading a descending component.
+  inverted: bool,
+}
+
+impl<'a> KeyReader<'a> {
+  pub fn new(bytes: &'a [u8]) -> Self {
+    Self {
+      bytes,
+      inverted: false,
+    }
+  }
+
+  /// Returns `true` if all bytes have been read.
+  pub fn is_empty(&self) -> bool {
+    self.bytes.is_empty()
+  }
+
+  pub fn read_byte(&mut self) -> Option<u8> {
+    let (&b, rest) = self.bytes.split_first()?;
+    self.bytes = rest;
+    Some(if self.inverted { !b } else { b })
+  }
+
+  pub fn read_array<const N: usize>(&mut self) -> Option<[u8; N]> {
+    let mut buf = [0u8; N];
+    for b in buf.iter_mut() {
+      *b = self.read_byte()?;
+    }
+    Some(buf)
+  }
+}
+
+/// Decodes a key encoded by [`ARTKey::to_bytes`] of a tuple or a single [`KeyComponent`].
+/// Returns `None` if `bytes` is malformed or has trailing bytes.
+///
+/// Note that a single `String` or `Vec<u8>` key is stored raw, unlike the same component in a
+/// tuple.
+pub fn decode_key<T: KeyComponent>(bytes: &[u8]) -> Option<T> {
+  T::decode_key(bytes)
+}
+
+macro_rules! impl_fixed_component {
+  ($($t:ty => $from:expr),*) => {
+    $(
+      impl KeyComponent for $t {
+        fn encode(&self, out: &mut Vec<u8>) {
+          out.extend_from_slice(&self.to_bytes());
+        }
+
+        fn decode(reader: &mut KeyReader<'_>) -> Option<Self> {
+          let from: fn([u8; std::mem::size_of::<$t>()]) -> Option<$t> = $from;
+          from(reader.read_array()?)
+        }
+      }
+    )*
+  };
+}
+
+macro_rules! impl_unsigned_component {
+  ($($t:ty),*) => {
+    impl_fixed_component!($($t => |b| Some(<$t>::from_be_bytes(b))),*);
+  };
+}
+
+macro_rules! impl_signed_component {
+  ($($t:ty => $u:ty),*) => {
+    impl_fixed_component!($(
+      $t => |b| Some((<$u>::from_be_bytes(b) ^ (1 << (<$u>::BITS - 1))) as $t)
+    ),*);
+  };
+}
+
+macro_rules! impl_float_component {
+  ($($t:ty => $u:ty),*) => {
+    impl_fixed_component!($(
+      $t => |b| {
+        let bits = <$u>::from_be_bytes(b);
+        let sign = 1 << (<$u>::BITS - 1);
+        let bits = if bits & sign != 0 { bits ^ sign } else { !bits };
+        Some(<$t>::from_bits(bits))
+      }
+    ),*);
+  };
+}
+
+impl_unsigned_component!(u8, u16, u32, u64, u128, usize);
+impl_signed_component!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+impl_float_component!(f32 => u32, f64 => u64);
+impl_fixed_component!(
+  bool => |b| match b {
+    [0] => Some(false),
+    [1] => Some(true),
+    _ => None,
+  },
+  char => |b| char::from_u32(u32::from_be_bytes(b))
+);
+
+/// Writes `bytes` with `0x00` escaped as `0x00 0xFF`, followed by terminator `0x00 0x00`.
+fn encode_escaped(bytes: &[u8], out: &mut Vec<u8>) {
+  for &b in bytes {
+    out.push(b);
+    if b == 0 {
+      out.push(0xFF);
+    }
+  }
+  out.extend_from_slice(&[0, 0]);
+}
+
+fn decode_escaped(reader: &mut KeyReader<'_>) -> Option<Vec<u8>> {
+  let mut bytes = Vec::new();
+  loop {
+    match reader.read_byte()? {
+      0 => match reader.read_byte()? {
+        0 => return Some(bytes),
+        0xFF => bytes.push(0),
+        _ => return None,
+      },
+      b => bytes.push(b),
+    }
+  }
+}
+
+impl KeyComponent for Vec<u8> {
+  fn encode(&self, out: &mut Vec<u8>) {
+    encode_escaped(self, out);
+  }
+
+  fn decode(reader: &mut KeyReader<'_>) -> Option<Self> {
+    decode_escaped(reader)
+  }
+
+  fn decode_key(bytes: &[u8]) -> Option<Self> {
+    Some(bytes.to_vec())
+  }
+}
+
+impl KeyComponent for String {
+  fn encode(&self, out: &mut Vec<u8>) {
+    encode_escaped(self.as_bytes(), out);
+  }
+
+  fn decode(reader: &mut KeyReader<'_>) -> Option<Self> {
+    String::from_utf8(decode_escaped(reader)?).ok()
+  }
+
+  fn decode_key(bytes: &[u8]) -> Option<Self> {
+    String::from_utf8(bytes.to_vec()).ok()
+  }
+}
+
+impl<T: KeyComponent> KeyComponent for Reverse<T> {
+  fn encode(&self, out: &mut Vec<u8>) {
+    let start = out.len();
+    self.0.encode(out);
+    out[start..].iter_mut().for_each(|b| *b = !*b);
+  }
+
+  fn decode(reader: &mut KeyReader<'_>) -> Option<Self> {
+    reader.inverted = !reader.inverted;
+    let value = T::decode(reader);
+    reader.inverted = !reader.inverted;
+    value.map(Reverse)
+  }
+}
+
+impl<T: KeyComponent> ARTKey for Reverse<T> {
+  type Bytes<'a>
+    = Vec<u8>
+  where
+    T: 'a;
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    let mut out = Vec::new();
+    self.encode(&mut out);
+    out
+  }
+}
+
+macro_rules! impl_tuple {
+  ($(($($name:ident: $idx:tt),+)),*) => {
+    $(
+      impl<$($name: KeyComponent),+> KeyComponent for ($($name,)+) {
+        fn encode(&self, out: &mut Vec<u8>) {
+          $(self.$idx.encode(out);)+
+        }
+
+        fn decode(reader: &mut KeyReader<'_>) -> Option<Self> {
+          Some(($($name::decode(reader)?,)+))
+        }
+      }
+
+      impl<$($name: KeyComponent),+> ARTKey for ($($name,)+) {
+        type Bytes<'a>
+          = Vec<u8>
+        where
+          Self: 'a;
+
+        fn to_bytes(&self) -> Self::Bytes<'_> {
+          let mut out = Vec::new();
+          self.encode(&mut out);
+          out
+        }
+      }
+    )*
+  };
+}
+
+impl_tuple!(
+  (A: 0),
+  (A: 0, B: 1),
+  (A: 0, B: 1, C: 2),
+  (A: 0, B: 1, C: 2, D: 3),
+  (A: 0, B: 1, C: 2, D: 3, E: 4),
+  (A: 0, B: 1, C: 2, D: 3, E: 4, F: 5),
+  (A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6),
+  (A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7)
+);
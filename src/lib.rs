@@ -1,12 +1,24 @@
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
 mod borrow;
+mod bulk;
+mod cursor;
 mod entry;
 mod insert;
+pub mod key;
 pub mod map;
 mod marker;
 mod navigate;
 mod node;
+mod remove;
 mod search;
+#[cfg(feature = "serde")]
+mod serde;
+pub mod set;
+mod stats;
 mod util;
+#[cfg(feature = "debug-invariants")]
+mod validate;
 
 pub(crate) use borrow::*;
 pub(crate) use util::*;
@@ -1,60 +1,127 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::mem;
+use std::mem::ManuallyDrop;
+use std::ops::{Bound, Index, RangeBounds};
+use std::ptr;
 use std::ptr::NonNull;
-use crate::entry::{Entry, OccupiedEntry};
-use crate::node::{BoxedNode, NodeRef};
-use crate::{DormantMutRef};
-use either::Either;
-use crate::marker::{Immut, InternalOrLeaf, Mut};
+
+use allocator_api2::alloc::{Allocator, Global};
+
+pub use crate::bulk::BulkLoadError;
+pub use crate::cursor::{Cursor, CursorMut};
+pub use crate::entry::{Entry, OccupiedEntry, VacantEntry};
+pub use crate::stats::{ARTStats, NodeClassStats};
+#[cfg(feature = "debug-invariants")]
+pub use crate::validate::{InvariantError, Violation};
+use crate::key::{bytes_bound, encode_bound, ARTBorrow, ARTKey};
+use crate::marker::{Dying, Immut, Internal, InternalOrLeaf, Leaf, Mut, ValMut};
+use crate::navigate::LeafRange;
+use crate::node::{BoxedNode, ChildPos, LeafNode, NodeArena, NodeImpl, NodeRef};
 use crate::search::SearchResult;
+use crate::{bulk, common_len, DormantMutRef};
 
-pub struct ARTMap<K, V> {
-  root: Option<BoxedNode<K, V>>,
+pub struct ARTMap<K, V, A: Allocator = Global> {
+  pub(crate) root: Option<BoxedNode<K, V>>,
+  pub(crate) length: usize,
+  /// Slabs all nodes of this map are allocated from.
+  pub(crate) arena: NodeArena<K, V, A>,
+}
+
+impl<K, V> Default for ARTMap<K, V> {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl<K, V> ARTMap<K, V> {
   pub fn new() -> Self {
-    Self { root: None }
+    Self::new_in(Global)
   }
+}
 
-  pub fn get(&self, key: &K) -> Option<&V>
+impl<K, V, A: Allocator> ARTMap<K, V, A> {
+  /// Makes a new, empty map whose nodes are allocated from `alloc`.
+  ///
+  /// Nodes are carved from chunks obtained from `alloc`, which are only returned when the map is
+  /// cleared or dropped.
+  pub fn new_in(alloc: A) -> Self {
+    Self {
+      root: None,
+      length: 0,
+      arena: NodeArena::new_in(alloc),
+    }
+  }
+
+  /// Returns a reference to the allocator of this map.
+  pub fn allocator(&self) -> &A {
+    self.arena.allocator()
+  }
+
+  /// Returns the number of elements in the map.
+  pub fn len(&self) -> usize {
+    self.length
+  }
+
+  /// Returns `true` if the map contains no elements.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns a reference to the value corresponding to the key.
+  ///
+  /// The key may be any borrowed form of the map's key type, e.g. `&str` or `&[u8]` for a map
+  /// keyed by `String`.
+  pub fn get<Q>(&self, key: &Q) -> Option<&V>
     where
-        K: AsRef<[u8]>,
+        K: ARTKey + ARTBorrow<Q>,
+        Q: ARTKey + ?Sized,
   {
-    match self.root_node_ref()?.search_tree(key) {
+    match self.root_node_ref()?.search_tree(key.to_bytes().as_ref()) {
       SearchResult::Found(leaf) => Some(leaf.value_ref()),
       SearchResult::NotFound(_) => None,
-      _ => unreachable!()
+      _ => unreachable!(),
     }
   }
 
-  pub fn get_mut(&mut self, key: &K) -> Option<&mut V>
+  /// Returns a mutable reference to the value corresponding to the key.
+  pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
     where
-        K: AsRef<[u8]>,
+        K: ARTKey + ARTBorrow<Q>,
+        Q: ARTKey + ?Sized,
   {
-    match self.root_node_mut()?.search_tree(key) {
+    match self.root_node_mut()?.search_tree(key.to_bytes().as_ref()) {
       SearchResult::Found(leaf) => Some(leaf.value_mut()),
       SearchResult::NotFound(_) => None,
-      _ => unreachable!()
+      _ => unreachable!(),
     }
   }
 
-  pub fn entry(&mut self, key: K) -> Entry<'_, K, V>
+  pub fn entry(&mut self, key: K) -> Entry<'_, K, V, A>
     where
-        K: AsRef<[u8]>,
+        K: ARTKey,
   {
-    let (map, dormant_ref) = DormantMutRef::new(self);
+    let (map, dormant_map) = DormantMutRef::new(self);
     match map.root_node_mut() {
-      Some(node) => match node.search_tree(&key) {
-        SearchResult::Found(leaf) => Entry::new_occupied(leaf),
-        SearchResult::NotFound(node) => Entry::new_vacant(key, Either::Right(node)),
-        _ => unreachable!()
-      },
-      None => Entry::new_vacant(key, Either::Left(NonNull::from(unsafe { &mut dormant_ref.awaken().root })))
+      Some(node) => {
+        let result = node.search_tree(key.to_bytes().as_ref());
+        match result {
+          SearchResult::Found(leaf) => Entry::new_occupied(key, leaf, dormant_map),
+          SearchResult::NotFound(node) => Entry::new_vacant(key, Some(node), dormant_map),
+          _ => unreachable!(),
+        }
+      }
+      None => Entry::new_vacant(key, None, dormant_map),
     }
   }
 
   pub fn insert(&mut self, key: K, value: V) -> Option<V>
     where
-        K: AsRef<[u8]>,
+        K: ARTKey,
   {
     match self.entry(key) {
       Entry::Occupied(mut entry) => Some(entry.insert(value)),
@@ -65,30 +132,878 @@ impl<K, V> ARTMap<K, V> {
     }
   }
 
-  pub fn remove(&mut self, key: &K) -> Option<V>
+  /// Clears the map, removing all elements.
+  ///
+  /// Nodes are released along with their slabs, so only keys and values are visited, and only if
+  /// they need to be dropped.
+  pub fn clear(&mut self) {
+    self.length = 0;
+    if let Some(root) = self.root.take() {
+      if mem::needs_drop::<(K, V)>() {
+        // SAFETY: The tree is detached from the map.
+        unsafe { NodeRef::<Dying, K, V, InternalOrLeaf>::root_node_ref(root).drop_key_values() }
+      }
+    }
+    // SAFETY: No node is reachable from the map.
+    unsafe { self.arena.release() }
+  }
+
+  /// Removes a key from the map, returning the value at the key if the key was previously in the
+  /// map.
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
     where
-        K: AsRef<[u8]>,
+        K: ARTKey + ARTBorrow<Q>,
+        Q: ARTKey + ?Sized,
   {
     self.remove_kv(key).map(|e| e.1)
   }
 
-  pub fn remove_kv(&mut self, key: &K) -> Option<(K, V)>
-    where K: AsRef<[u8]>,
+  /// Removes a key from the map, returning the stored key and value if the key was previously in
+  /// the map.
+  pub fn remove_kv<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where K: ARTKey + ARTBorrow<Q>,
+          Q: ARTKey + ?Sized,
   {
-    match self.root_node_mut()?.search_tree(key) {
-      SearchResult::Found(leaf) => Some(OccupiedEntry { node: leaf }.remove_kv()),
+    let (map, dormant_map) = DormantMutRef::new(self);
+    match map.root_node_mut()?.search_tree(key.to_bytes().as_ref()) {
+      SearchResult::Found(leaf) => Some(
+        OccupiedEntry {
+          key: None,
+          node: leaf,
+          dormant_map,
+        }
+        .remove_entry(),
+      ),
       SearchResult::NotFound(_) => None,
-      _ => unreachable!()
+      _ => unreachable!(),
+    }
+  }
+
+  /// Returns the first key-value pair in the map, i.e. the one with minimum key bytes.
+  pub fn first_key_value(&self) -> Option<(&K, &V)> {
+    Some(self.root_node_ref()?.minimum_leaf().into_kv_ref())
+  }
+
+  /// Returns the last key-value pair in the map, i.e. the one with maximum key bytes.
+  pub fn last_key_value(&self) -> Option<(&K, &V)> {
+    Some(self.root_node_ref()?.maximum_leaf().into_kv_ref())
+  }
+
+  /// Returns the first entry in the map for in-place manipulation.
+  pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, K, V, A>> {
+    let (map, dormant_map) = DormantMutRef::new(self);
+    let leaf = map.root_node_mut()?.minimum_leaf();
+    Some(OccupiedEntry {
+      key: None,
+      node: leaf,
+      dormant_map,
+    })
+  }
+
+  /// Returns the last entry in the map for in-place manipulation.
+  pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, K, V, A>> {
+    let (map, dormant_map) = DormantMutRef::new(self);
+    let leaf = map.root_node_mut()?.maximum_leaf();
+    Some(OccupiedEntry {
+      key: None,
+      node: leaf,
+      dormant_map,
+    })
+  }
+
+  /// Removes and returns the first element in the map.
+  pub fn pop_first(&mut self) -> Option<(K, V)> {
+    self.first_entry().map(|entry| entry.remove_entry())
+  }
+
+  /// Removes and returns the last element in the map.
+  pub fn pop_last(&mut self) -> Option<(K, V)> {
+    self.last_entry().map(|entry| entry.remove_entry())
+  }
+
+  /// Gets an iterator over the entries of the map, sorted by key bytes.
+  pub fn iter(&self) -> Iter<'_, K, V> {
+    Iter {
+      range: LeafRange::from_root(self.root_node_ref()),
+      length: self.length,
     }
   }
+
+  /// Gets a mutable iterator over the entries of the map, sorted by key bytes.
+  pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+    let length = self.length;
+    IterMut {
+      range: LeafRange::from_root(self.root_node_val_mut()),
+      length,
+      _marker: PhantomData,
+    }
+  }
+
+  /// Constructs a double-ended iterator over a sub-range of entries in the map, whose keys are
+  /// in `range`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if range `start > end`, or if range `start == end` and both bounds are `Excluded`.
+  pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+  where
+    K: ARTKey + ARTBorrow<Q>,
+    Q: ARTKey + ?Sized,
+    R: RangeBounds<Q>,
+  {
+    let start = encode_bound(range.start_bound());
+    let end = encode_bound(range.end_bound());
+    Range {
+      range: self.root_node_ref().map_or_else(LeafRange::none, |root| {
+        LeafRange::range_search(root, bytes_bound(&start), bytes_bound(&end))
+      }),
+    }
+  }
+
+  /// Constructs a mutable double-ended iterator over a sub-range of entries in the map, whose
+  /// keys are in `range`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if range `start > end`, or if range `start == end` and both bounds are `Excluded`.
+  pub fn range_mut<Q, R>(&mut self, range: R) -> RangeMut<'_, K, V>
+  where
+    K: ARTKey + ARTBorrow<Q>,
+    Q: ARTKey + ?Sized,
+    R: RangeBounds<Q>,
+  {
+    let start = encode_bound(range.start_bound());
+    let end = encode_bound(range.end_bound());
+    RangeMut {
+      range: self.root_node_val_mut().map_or_else(LeafRange::none, |root| {
+        LeafRange::range_search(root, bytes_bound(&start), bytes_bound(&end))
+      }),
+      _marker: PhantomData,
+    }
+  }
+
+  /// Constructs a double-ended iterator over entries whose key bytes start with `prefix`.
+  pub fn prefix_iter(&self, prefix: &[u8]) -> Range<'_, K, V>
+  where
+    K: ARTKey,
+  {
+    Range {
+      range: self
+        .root_node_ref()
+        .and_then(|root| root.search_prefix(prefix))
+        .map_or_else(LeafRange::none, LeafRange::full),
+    }
+  }
+
+  /// Constructs a mutable double-ended iterator over entries whose key bytes start with `prefix`.
+  pub fn prefix_iter_mut(&mut self, prefix: &[u8]) -> RangeMut<'_, K, V>
+  where
+    K: ARTKey,
+  {
+    RangeMut {
+      range: self
+        .root_node_val_mut()
+        .and_then(|root| root.search_prefix(prefix))
+        .map_or_else(LeafRange::none, LeafRange::full),
+      _marker: PhantomData,
+    }
+  }
+
+  /// Returns `true` if the map contains any key starting with `prefix`.
+  pub fn has_prefix(&self, prefix: &[u8]) -> bool
+  where
+    K: ARTKey,
+  {
+    self
+      .root_node_ref()
+      .and_then(|root| root.search_prefix(prefix))
+      .is_some()
+  }
+
+  /// Returns the first entry whose key bytes start with `prefix`.
+  pub fn first_with_prefix(&self, prefix: &[u8]) -> Option<(&K, &V)>
+  where
+    K: ARTKey,
+  {
+    let node = self.root_node_ref()?.search_prefix(prefix)?;
+    Some(node.minimum_leaf().into_kv_ref())
+  }
+
+  /// Returns the last entry whose key bytes start with `prefix`.
+  pub fn last_with_prefix(&self, prefix: &[u8]) -> Option<(&K, &V)>
+  where
+    K: ARTKey,
+  {
+    let node = self.root_node_ref()?.search_prefix(prefix)?;
+    Some(node.maximum_leaf().into_kv_ref())
+  }
+
+  /// Returns a [`Cursor`] pointing at the first element whose key is above the given bound.
+  ///
+  /// If no such element exists then a cursor pointing at the "ghost" non-element is returned.
+  pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, K, V>
+  where
+    K: ARTKey + ARTBorrow<Q>,
+    Q: ARTKey + ?Sized,
+  {
+    Cursor::lower_bound(self, bytes_bound(&encode_bound(bound)))
+  }
+
+  /// Returns a [`CursorMut`] pointing at the first element whose key is above the given bound.
+  ///
+  /// If no such element exists then a cursor pointing at the "ghost" non-element is returned.
+  pub fn lower_bound_mut<Q>(&mut self, bound: Bound<&Q>) -> CursorMut<'_, K, V, A>
+  where
+    K: ARTKey + ARTBorrow<Q>,
+    Q: ARTKey + ?Sized,
+  {
+    CursorMut::lower_bound(self, bytes_bound(&encode_bound(bound)))
+  }
+
+  /// Returns a [`Cursor`] pointing at the last element whose key is below the given bound.
+  ///
+  /// If no such element exists then a cursor pointing at the "ghost" non-element is returned.
+  pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, K, V>
+  where
+    K: ARTKey + ARTBorrow<Q>,
+    Q: ARTKey + ?Sized,
+  {
+    Cursor::upper_bound(self, bytes_bound(&encode_bound(bound)))
+  }
+
+  /// Returns a [`CursorMut`] pointing at the last element whose key is below the given bound.
+  ///
+  /// If no such element exists then a cursor pointing at the "ghost" non-element is returned.
+  pub fn upper_bound_mut<Q>(&mut self, bound: Bound<&Q>) -> CursorMut<'_, K, V, A>
+  where
+    K: ARTKey + ARTBorrow<Q>,
+    Q: ARTKey + ?Sized,
+  {
+    CursorMut::upper_bound(self, bytes_bound(&encode_bound(bound)))
+  }
+
+  /// Gets an iterator over the keys of the map, in sorted order.
+  pub fn keys(&self) -> Keys<'_, K, V> {
+    Keys { inner: self.iter() }
+  }
+
+  /// Gets an iterator over the values of the map, in order by key.
+  pub fn values(&self) -> Values<'_, K, V> {
+    Values { inner: self.iter() }
+  }
+
+  /// Gets a mutable iterator over the values of the map, in order by key.
+  pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+    ValuesMut {
+      inner: self.iter_mut(),
+    }
+  }
+
+  /// Collects node counts, shape histograms and estimated memory usage of the tree. This visits
+  /// every node, so it takes linear time.
+  pub fn stats(&self) -> ARTStats
+    where
+        K: ARTKey,
+  {
+    ARTStats::new(self.root_node_ref(), &self.arena)
+  }
 }
 
-impl<K, V> ARTMap<K, V> {
-  fn root_node_ref(&self) -> Option<NodeRef<Immut<'_>, K, V, InternalOrLeaf>> {
-    self.root.map(|ptr| NodeRef::root_node_ref(ptr, NonNull::from(&self.root)))
+impl<K, V, A: Allocator> ARTMap<K, V, A> {
+  pub(crate) fn root_node_ref(&self) -> Option<NodeRef<Immut<'_>, K, V, InternalOrLeaf>> {
+    self.root.map(|ptr| NodeRef::root_node_ref(ptr))
+  }
+
+  pub(crate) fn root_node_mut(&mut self) -> Option<NodeRef<Mut<'_>, K, V, InternalOrLeaf>> {
+    self.root.map(|ptr| NodeRef::root_node_ref(ptr))
+  }
+
+  fn root_node_val_mut(&mut self) -> Option<NodeRef<ValMut<'_>, K, V, InternalOrLeaf>> {
+    self.root.map(|ptr| NodeRef::root_node_ref(ptr))
+  }
+}
+
+impl<K: ARTKey, V> ARTMap<K, V> {
+  /// Builds a map from entries in ascending order of key bytes. The tree is built bottom-up, so
+  /// each node is allocated once with its final class and partial key.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if keys are not strictly ascending.
+  pub fn from_sorted_iter<I>(iter: I) -> Result<Self, BulkLoadError>
+  where
+    I: IntoIterator<Item = (K, V)>,
+  {
+    let (map, result) = bulk::bulk_load(Global, None, iter, |_, _, _| false);
+    result.map(|_| map)
+  }
+
+  /// Same as [`from_sorted_iter`](Self::from_sorted_iter), but consecutive entries with same key
+  /// are allowed. For each of them, `resolve` is called with the key and value kept in the map and
+  /// the new value, e.g. `|_, old, new| *old = new` keeps the last value.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if keys are not ascending.
+  pub fn from_sorted_iter_with<I, F>(iter: I, mut resolve: F) -> Result<Self, BulkLoadError>
+  where
+    I: IntoIterator<Item = (K, V)>,
+    F: FnMut(&K, &mut V, V),
+  {
+    let (map, result) = bulk::bulk_load(Global, None, iter, |key, old, new| {
+      resolve(key, old, new);
+      true
+    });
+    result.map(|_| map)
+  }
+}
+
+impl<K: ARTKey, V, A: Allocator + Clone> ARTMap<K, V, A> {
+  /// Moves entries in ascending order of key bytes into the map. Existing entries are merged with
+  /// the input, and the tree is rebuilt bottom-up.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if keys are not strictly ascending, or an input key already exists in the
+  /// map. Entries before the erroneous one are kept in the map, and the rest are dropped.
+  pub fn bulk_extend_sorted<I>(&mut self, iter: I) -> Result<(), BulkLoadError>
+  where
+    I: IntoIterator<Item = (K, V)>,
+  {
+    let alloc = self.allocator().clone();
+    let old = mem::replace(self, ARTMap::new_in(alloc.clone())).into_iter();
+    let (map, result) = bulk::bulk_load(alloc, Some(old), iter, |_, _, _| false);
+    *self = map;
+    result
+  }
+
+  /// Same as [`bulk_extend_sorted`](Self::bulk_extend_sorted), but duplicate keys are resolved by
+  /// `resolve` as in [`from_sorted_iter_with`](Self::from_sorted_iter_with). When an input key
+  /// already exists in the map, the existing entry is the one kept.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if keys are not ascending. Entries before the erroneous one are kept in the
+  /// map, and the rest are dropped.
+  pub fn bulk_extend_sorted_with<I, F>(
+    &mut self,
+    iter: I,
+    mut resolve: F,
+  ) -> Result<(), BulkLoadError>
+  where
+    I: IntoIterator<Item = (K, V)>,
+    F: FnMut(&K, &mut V, V),
+  {
+    let alloc = self.allocator().clone();
+    let old = mem::replace(self, ARTMap::new_in(alloc.clone())).into_iter();
+    let (map, result) = bulk::bulk_load(alloc, Some(old), iter, |key, old, new| {
+      resolve(key, old, new);
+      true
+    });
+    *self = map;
+    result
+  }
+}
+
+impl<K: ARTKey, V, A: Allocator> ARTMap<K, V, A> {
+  /// Inserts `key`, `value` into the map, whose maximum key is stored in `last`. When `key` is
+  /// greater than it, search starts from the deepest ancestor of `last` sharing prefix with `key`
+  /// instead of root, so inserting keys in order doesn't walk down from root every time. Other
+  /// keys are inserted as usual, and an existing value is overwritten.
+  ///
+  /// Returns the leaf with maximum key after insertion.
+  ///
+  /// # Safety
+  ///
+  /// `last` must be `None`, or the leaf with maximum key in this map.
+  #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+  pub(crate) unsafe fn insert_after(
+    &mut self,
+    last: Option<NonNull<LeafNode<K, V>>>,
+    key: K,
+    value: V,
+  ) -> NonNull<LeafNode<K, V>> {
+    let last_leaf = match last {
+      Some(last) => NodeRef::<Mut<'_>, K, V, Leaf>::from_node_ptr(last.cast()),
+      None => {
+        self.insert(key, value);
+        let last_leaf = self.root_node_mut().unwrap().maximum_leaf();
+        return last_leaf.get_inner().cast();
+      }
+    };
+
+    let common_key_len = {
+      let input_key = key.to_bytes();
+      let last_key = last_leaf.key_bytes();
+      if input_key.as_ref() <= last_key.as_ref() {
+        None
+      } else {
+        Some(common_len(input_key.as_ref(), last_key.as_ref()))
+      }
+    };
+    let mut node = match common_key_len {
+      Some(common_key_len) => {
+        let mut node = last_leaf.forget_type();
+        // Path from root to this node only consumes bytes shared by both keys.
+        while node.prefix_len() > common_key_len {
+          node = node.ascend().unwrap().forget_type();
+        }
+        node
+      }
+      None => {
+        self.insert(key, value);
+        return last_leaf.get_inner().cast();
+      }
+    };
+
+    let result = node.search_tree(key.to_bytes().as_ref());
+    match result {
+      SearchResult::NotFound(found) => node = found,
+      _ => unreachable!(),
+    }
+    let new_leaf = node.insert_node(key, value, NonNull::from(&mut self.root), &mut self.arena);
+    // Counted only once the leaf is linked, in case encoding the key panics.
+    self.length += 1;
+    new_leaf
+  }
+}
+
+impl<K, V, A: Allocator> Drop for ARTMap<K, V, A> {
+  fn drop(&mut self) {
+    self.clear();
+  }
+}
+
+impl<K: Clone, V: Clone, A: Allocator + Clone> Clone for ARTMap<K, V, A> {
+  /// Copies node structure of this map, so node classes and partial keys are kept as is.
+  fn clone(&self) -> Self {
+    let mut out = ARTMap::new_in(self.allocator().clone());
+    // Each node is copied and attached to the copy of its parent right away, so the out tree can
+    // be dropped if cloning a key or value panics.
+    let mut stack: Vec<_> = self
+      .root_node_ref()
+      .map(|root| (root, None))
+      .into_iter()
+      .collect();
+    while let Some((node, holder)) = stack.pop() {
+      let new_node = match node.downcast() {
+        NodeImpl::Leaf(leaf) => {
+          let (k, v) = leaf.into_kv_ref();
+          let new_leaf = out.arena.alloc(LeafNode::new(k.clone(), v.clone()));
+          out.length += 1;
+          new_leaf.cast()
+        }
+        NodeImpl::Internal(internal) => {
+          let new_node = internal.clone_empty(&mut out.arena);
+          if let Some(leaf) = internal.get_leaf() {
+            stack.push((leaf.forget_type(), Some((new_node, ChildPos::from(None)))));
+          }
+          internal.for_each_child(|k, child| {
+            stack.push((child, Some((new_node, ChildPos::from(Some(k))))))
+          });
+          new_node
+        }
+      };
+      match holder {
+        Some((parent, pos)) => unsafe {
+          NodeRef::<Mut<'_>, K, V, Internal>::root_node_ref(parent)
+            .update_child_at(pos, Some(new_node));
+        },
+        None => out.root = Some(new_node),
+      }
+    }
+    out
+  }
+}
+
+impl<K: Debug, V: Debug, A: Allocator> Debug for ARTMap<K, V, A> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_map().entries(self.iter()).finish()
   }
+}
+
+impl<K: PartialEq, V: PartialEq, A: Allocator> PartialEq for ARTMap<K, V, A> {
+  fn eq(&self, other: &Self) -> bool {
+    self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a == b)
+  }
+}
+
+impl<K: Eq, V: Eq, A: Allocator> Eq for ARTMap<K, V, A> {}
+
+impl<K: Hash, V: Hash, A: Allocator> Hash for ARTMap<K, V, A> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    state.write_usize(self.len());
+    for elt in self {
+      elt.hash(state);
+    }
+  }
+}
 
-  fn root_node_mut(&mut self) -> Option<NodeRef<Mut<'_>, K, V, InternalOrLeaf>> {
-    self.root.map(|ptr| NodeRef::root_node_ref(ptr, NonNull::from(&self.root)))
+impl<K: PartialOrd, V: PartialOrd, A: Allocator> PartialOrd for ARTMap<K, V, A> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.iter().partial_cmp(other.iter())
   }
 }
+
+impl<K: Ord, V: Ord, A: Allocator> Ord for ARTMap<K, V, A> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.iter().cmp(other.iter())
+  }
+}
+
+impl<K, Q, V, A: Allocator> Index<&Q> for ARTMap<K, V, A>
+where
+  K: ARTKey + ARTBorrow<Q>,
+  Q: ARTKey + ?Sized,
+{
+  type Output = V;
+
+  /// Returns a reference to the value corresponding to the supplied key.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the key is not present in the `ARTMap`.
+  fn index(&self, key: &Q) -> &V {
+    self.get(key).expect("no entry found for key")
+  }
+}
+
+impl<K: ARTKey, V> FromIterator<(K, V)> for ARTMap<K, V> {
+  fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+    let mut map = ARTMap::new();
+    map.extend(iter);
+    map
+  }
+}
+
+impl<K: ARTKey, V, A: Allocator> Extend<(K, V)> for ARTMap<K, V, A> {
+  fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+    iter.into_iter().for_each(move |(k, v)| {
+      self.insert(k, v);
+    });
+  }
+}
+
+impl<'a, K: ARTKey + Copy, V: Copy, A: Allocator> Extend<(&'a K, &'a V)> for ARTMap<K, V, A> {
+  fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: I) {
+    self.extend(iter.into_iter().map(|(&key, &value)| (key, value)));
+  }
+}
+
+/// An iterator over the entries of an `ARTMap`.
+pub struct Iter<'a, K, V> {
+  range: LeafRange<Immut<'a>, K, V>,
+  length: usize,
+}
+
+/// A mutable iterator over the entries of an `ARTMap`.
+pub struct IterMut<'a, K, V> {
+  range: LeafRange<ValMut<'a>, K, V>,
+  length: usize,
+  _marker: PhantomData<&'a mut (K, V)>,
+}
+
+/// An owning iterator over the entries of an `ARTMap`.
+pub struct IntoIter<K, V, A: Allocator = Global> {
+  range: LeafRange<Dying, K, V>,
+  length: usize,
+  /// Keeps nodes alive until the iterator is dropped.
+  _arena: NodeArena<K, V, A>,
+}
+
+/// An iterator over a sub-range of entries in an `ARTMap`.
+pub struct Range<'a, K, V> {
+  range: LeafRange<Immut<'a>, K, V>,
+}
+
+/// A mutable iterator over a sub-range of entries in an `ARTMap`.
+pub struct RangeMut<'a, K, V> {
+  range: LeafRange<ValMut<'a>, K, V>,
+  _marker: PhantomData<&'a mut (K, V)>,
+}
+
+/// An iterator over the keys of an `ARTMap`.
+pub struct Keys<'a, K, V> {
+  inner: Iter<'a, K, V>,
+}
+
+/// An iterator over the values of an `ARTMap`.
+pub struct Values<'a, K, V> {
+  inner: Iter<'a, K, V>,
+}
+
+/// A mutable iterator over the values of an `ARTMap`.
+pub struct ValuesMut<'a, K, V> {
+  inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V, A: Allocator> IntoIterator for &'a ARTMap<K, V, A> {
+  type Item = (&'a K, &'a V);
+  type IntoIter = Iter<'a, K, V>;
+
+  fn into_iter(self) -> Iter<'a, K, V> {
+    self.iter()
+  }
+}
+
+impl<'a, K, V, A: Allocator> IntoIterator for &'a mut ARTMap<K, V, A> {
+  type Item = (&'a K, &'a mut V);
+  type IntoIter = IterMut<'a, K, V>;
+
+  fn into_iter(self) -> IterMut<'a, K, V> {
+    self.iter_mut()
+  }
+}
+
+impl<K, V, A: Allocator> IntoIterator for ARTMap<K, V, A> {
+  type Item = (K, V);
+  type IntoIter = IntoIter<K, V, A>;
+
+  fn into_iter(self) -> IntoIter<K, V, A> {
+    let mut me = ManuallyDrop::new(self);
+    let root = me.root.take();
+    IntoIter {
+      range: LeafRange::from_root(root.map(NodeRef::root_node_ref)),
+      length: me.length,
+      // SAFETY: The map is forgotten, so the arena is only owned by the iterator.
+      _arena: unsafe { ptr::read(&me.arena) },
+    }
+  }
+}
+
+impl<'a, K, V> Clone for Iter<'a, K, V> {
+  fn clone(&self) -> Self {
+    Iter {
+      range: self.range.clone(),
+      length: self.length,
+    }
+  }
+}
+
+impl<'a, K: 'a, V: 'a> Iterator for Iter<'a, K, V> {
+  type Item = (&'a K, &'a V);
+
+  fn next(&mut self) -> Option<(&'a K, &'a V)> {
+    if self.length == 0 {
+      None
+    } else {
+      self.length -= 1;
+      self.range.next_checked().map(NodeRef::into_kv_ref)
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.length, Some(self.length))
+  }
+}
+
+impl<'a, K: 'a, V: 'a> DoubleEndedIterator for Iter<'a, K, V> {
+  fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+    if self.length == 0 {
+      None
+    } else {
+      self.length -= 1;
+      self.range.next_back_checked().map(NodeRef::into_kv_ref)
+    }
+  }
+}
+
+impl<'a, K: 'a, V: 'a> ExactSizeIterator for Iter<'a, K, V> {}
+
+impl<'a, K: 'a, V: 'a> FusedIterator for Iter<'a, K, V> {}
+
+impl<'a, K: 'a, V: 'a> Iterator for IterMut<'a, K, V> {
+  type Item = (&'a K, &'a mut V);
+
+  fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+    if self.length == 0 {
+      None
+    } else {
+      self.length -= 1;
+      self.range.next_checked().map(NodeRef::into_kv_valmut)
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.length, Some(self.length))
+  }
+}
+
+impl<'a, K: 'a, V: 'a> DoubleEndedIterator for IterMut<'a, K, V> {
+  fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+    if self.length == 0 {
+      None
+    } else {
+      self.length -= 1;
+      self.range.next_back_checked().map(NodeRef::into_kv_valmut)
+    }
+  }
+}
+
+impl<'a, K: 'a, V: 'a> ExactSizeIterator for IterMut<'a, K, V> {}
+
+impl<'a, K: 'a, V: 'a> FusedIterator for IterMut<'a, K, V> {}
+
+impl<K, V, A: Allocator> Iterator for IntoIter<K, V, A> {
+  type Item = (K, V);
+
+  fn next(&mut self) -> Option<(K, V)> {
+    if self.length == 0 {
+      None
+    } else {
+      self.length -= 1;
+      // SAFETY: Each leaf is visited only once.
+      self.range.next_checked().map(|leaf| unsafe { leaf.read_kv() })
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.length, Some(self.length))
+  }
+}
+
+impl<K, V, A: Allocator> DoubleEndedIterator for IntoIter<K, V, A> {
+  fn next_back(&mut self) -> Option<(K, V)> {
+    if self.length == 0 {
+      None
+    } else {
+      self.length -= 1;
+      // SAFETY: Each leaf is visited only once.
+      self.range.next_back_checked().map(|leaf| unsafe { leaf.read_kv() })
+    }
+  }
+}
+
+impl<K, V, A: Allocator> ExactSizeIterator for IntoIter<K, V, A> {}
+
+impl<K, V, A: Allocator> FusedIterator for IntoIter<K, V, A> {}
+
+impl<K, V, A: Allocator> Drop for IntoIter<K, V, A> {
+  fn drop(&mut self) {
+    if mem::needs_drop::<(K, V)>() {
+      self.for_each(drop);
+    }
+  }
+}
+
+impl<'a, K, V> Clone for Range<'a, K, V> {
+  fn clone(&self) -> Self {
+    Range {
+      range: self.range.clone(),
+    }
+  }
+}
+
+impl<'a, K: 'a, V: 'a> Iterator for Range<'a, K, V> {
+  type Item = (&'a K, &'a V);
+
+  fn next(&mut self) -> Option<(&'a K, &'a V)> {
+    self.range.next_checked().map(NodeRef::into_kv_ref)
+  }
+}
+
+impl<'a, K: 'a, V: 'a> DoubleEndedIterator for Range<'a, K, V> {
+  fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+    self.range.next_back_checked().map(NodeRef::into_kv_ref)
+  }
+}
+
+impl<'a, K: 'a, V: 'a> FusedIterator for Range<'a, K, V> {}
+
+impl<'a, K: 'a, V: 'a> Iterator for RangeMut<'a, K, V> {
+  type Item = (&'a K, &'a mut V);
+
+  fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+    self.range.next_checked().map(NodeRef::into_kv_valmut)
+  }
+}
+
+impl<'a, K: 'a, V: 'a> DoubleEndedIterator for RangeMut<'a, K, V> {
+  fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+    self.range.next_back_checked().map(NodeRef::into_kv_valmut)
+  }
+}
+
+impl<'a, K: 'a, V: 'a> FusedIterator for RangeMut<'a, K, V> {}
+
+impl<'a, K, V> Clone for Keys<'a, K, V> {
+  fn clone(&self) -> Self {
+    Keys {
+      inner: self.inner.clone(),
+    }
+  }
+}
+
+impl<'a, K: 'a, V: 'a> Iterator for Keys<'a, K, V> {
+  type Item = &'a K;
+
+  fn next(&mut self) -> Option<&'a K> {
+    self.inner.next().map(|(k, _)| k)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<'a, K: 'a, V: 'a> DoubleEndedIterator for Keys<'a, K, V> {
+  fn next_back(&mut self) -> Option<&'a K> {
+    self.inner.next_back().map(|(k, _)| k)
+  }
+}
+
+impl<'a, K: 'a, V: 'a> ExactSizeIterator for Keys<'a, K, V> {}
+
+impl<'a, K: 'a, V: 'a> FusedIterator for Keys<'a, K, V> {}
+
+impl<'a, K, V> Clone for Values<'a, K, V> {
+  fn clone(&self) -> Self {
+    Values {
+      inner: self.inner.clone(),
+    }
+  }
+}
+
+impl<'a, K: 'a, V: 'a> Iterator for Values<'a, K, V> {
+  type Item = &'a V;
+
+  fn next(&mut self) -> Option<&'a V> {
+    self.inner.next().map(|(_, v)| v)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<'a, K: 'a, V: 'a> DoubleEndedIterator for Values<'a, K, V> {
+  fn next_back(&mut self) -> Option<&'a V> {
+    self.inner.next_back().map(|(_, v)| v)
+  }
+}
+
+impl<'a, K: 'a, V: 'a> ExactSizeIterator for Values<'a, K, V> {}
+
+impl<'a, K: 'a, V: 'a> FusedIterator for Values<'a, K, V> {}
+
+impl<'a, K: 'a, V: 'a> Iterator for ValuesMut<'a, K, V> {
+  type Item = &'a mut V;
+
+  fn next(&mut self) -> Option<&'a mut V> {
+    self.inner.next().map(|(_, v)| v)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<'a, K: 'a, V: 'a> DoubleEndedIterator for ValuesMut<'a, K, V> {
+  fn next_back(&mut self) -> Option<&'a mut V> {
+    self.inner.next_back().map(|(_, v)| v)
+  }
+}
+
+impl<'a, K: 'a, V: 'a> ExactSizeIterator for ValuesMut<'a, K, V> {}
+
+impl<'a, K: 'a, V: 'a> FusedIterator for ValuesMut<'a, K, V> {}
@@ -3,13 +3,17 @@ use std::marker::PhantomData;
 /// Borrow type of a node.
 pub trait BorrowType {}
 
-pub enum Owned {}
 pub struct Immut<'a>(PhantomData<&'a ()>);
 pub struct Mut<'a>(PhantomData<&'a mut ()>);
+/// Like `Mut`, but only values are mutable, so multiple refs of this type may coexist.
+pub struct ValMut<'a>(PhantomData<&'a mut ()>);
+/// The tree is being destroyed, nodes may be deallocated after their contents are moved out.
+pub enum Dying {}
 
-impl BorrowType for Owned {}
 impl<'a> BorrowType for Immut<'a> {}
 impl<'a> BorrowType for Mut<'a> {}
+impl<'a> BorrowType for ValMut<'a> {}
+impl BorrowType for Dying {}
 
 /// Node Types
 pub enum InternalOrLeaf {}
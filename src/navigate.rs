@@ -1,142 +1,335 @@
-// use std::cmp::Ordering;
-// use crate::node::{BoxedLeafNode, Handle, InternalNodeRef, PartialKey};
-// use crate::node::LeafNodeRef;
-// use crate::node::{NodeKind, NodeRef, DEFAULT_TREE_DEPTH};
-//
-// pub(crate) enum SearchResult<BorrowType, V> {
-//   GoUp,
-//   Found(LeafNodeRef<BorrowType, V>),
-//   GoDown(InternalNodeRef<BorrowType, V>),
-// }
-//
-// #[derive(Copy, Clone)]
-// pub(crate) struct SearchArgument<'a> {
-//   key: &'a [u8],
-//   depth: usize,
-// }
-//
-// impl<BorrowType, V> NodeRef<BorrowType, V> {
-//   /// Find first leaf node, whose keys is not less than input key.
-//   pub(crate) fn find_lower_bound_node(self, key: &[u8]) -> Option<LeafNodeRef<BorrowType, V>> {
-//     let mut stack = Vec::<Handle<BorrowType, V>>::with_capacity(DEFAULT_TREE_DEPTH);
-//
-//     let mut cur = self;
-//     let mut depth = 0usize;
-//     let mut arg = SearchArgument { key, depth: 0 };
-//
-//     loop {
-//       match cur.downcast() {
-//         NodeKind::Internal(node) => match node.search_lower_bound(key, depth) {
-//           SearchResult::GoUp => break,
-//           SearchResult::Found(ret) => {
-//             return Some(ret);
-//           }
-//           SearchResult::GoDown(origin_node) => {
-//             let new_depth = depth + origin_node.partial_key().len();
-//             let k = key[new_depth];
-//             if let Some(c) = node.find_child(k) {
-//               stack.push(c.reborrow());
-//               depth = new_depth + 1;
-//               cur = c.reborrow().into();
-//             } else {
-//               break;
-//             }
-//           }
-//         },
-//         NodeKind::Leaf(node) => {
-//           if node.is_lower_bound(arg) {
-//             return Some(node);
-//           } else {
-//             break;
-//           }
-//         }
-//       }
-//     }
-//
-//     while let Some(entry) = stack.pop() {
-//       if let Some(c) = entry.node.find_next_child(entry.key) {
-//         return Some(c.minimum_leaf());
-//       }
-//     }
-//
-//     None
-//   }
-// }
-//
-// impl<'a> SearchArgument<'a> {
-//   #[inline(always)]
-//   pub(crate) fn partial_key(&self) -> &[u8] {
-//     // This is possible when that last node of key matches some internal node,
-//     // and we need to go down.
-//     if self.depth >= self.key.len() {
-//       &[0u8; 0]
-//     } else {
-//       &self.key[self.depth..]
-//     }
-//   }
-//
-//   #[inline(always)]
-//   pub(crate) fn depth(&self) -> usize {
-//     self.depth
-//   }
-// }
-//
-// impl<BorrowType, V> InternalNodeRef<BorrowType, V> {
-//   fn search_lower_bound(self, key: &[u8], depth: usize) -> SearchResult<LeafNodeRef<BorrowType, V>> {
-//     let this_partial_prefix = self.partial_prefix();
-//     let input_partial_key = &key[depth..];
-//
-//     match input_partial_key.cmp(this_partial_prefix) {
-//       Ordering::Greater => SearchResult::GoUp,
-//       Ordering::Equal => {
-//         match self.partial_key() {
-//           PartialKey::Prefix(_) => SearchResult::GoDown(depth + this_partial_prefix.len()),
-//           PartialKey::Leaf(leaf_node) => SearchResult::Found(leaf_node.into())
-//         }
-//       }
-//       Ordering::Less => {
-//         match self.partial_key() {
-//           PartialKey::Prefix(_) => SearchResult::Found(self.minimum_leaf()),
-//           PartialKey::Leaf(leaf_node) => SearchResult::Found(leaf_node.into())
-//         }
-//       }
-//     }
-//   }
-//
-//   pub(crate) fn minimum_leaf(self) -> Option<LeafNodeRef<BorrowType, V>> {
-//     todo!()
-//   }
-//
-//   pub(crate) fn maximum_leaf(self) -> Option<LeafNodeRef<BorrowType, V>> {
-//     todo!()
-//   }
-//
-//   fn search_upper_bound(self, key: &[u8], depth: usize) -> SearchResult<LeafNodeRef<BorrowType, V>> {
-//     let this_partial_prefix = self.partial_prefix();
-//     let input_partial_key = &key[depth..];
-//
-//     match input_partial_key.cmp(this_partial_prefix) {
-//       Ordering::Less => SearchResult::GoUp,
-//       Ordering::Equal => {
-//         match self.partial_key() {
-//           PartialKey::Prefix(_) => SearchResult::GoDown(depth + this_partial_prefix.len()),
-//           PartialKey::Leaf(leaf_node) => SearchResult::Found(leaf_node.into())
-//         }
-//       }
-//       Ordering::Greater => {
-//         match self.partial_key() {
-//           PartialKey::Prefix(_) => SearchResult::Found(self.maximum_leaf()),
-//           PartialKey::Leaf(leaf_node) => SearchResult::Found(leaf_node.into())
-//         }
-//       }
-//     }
-//   }
-//
-//   pub(crate) fn find_child(&self, _k: u8) -> Option<Handle<BorrowType, V>> {
-//     todo!()
-//   }
-//
-//   pub(crate) fn find_next_child(&self, _k: u8) -> Option<NodeRef<BorrowType, V>> {
-//     todo!()
-//   }
-// }
+use std::cmp::Ordering;
+use std::ops::Bound;
+use std::ops::Bound::{Excluded, Included, Unbounded};
+
+use crate::key::ARTKey;
+use crate::marker::{Immut, Internal, InternalOrLeaf, Leaf};
+use crate::node::{NodeImpl, NodeRef};
+
+enum BoundSearchResult<BorrowType, K, V> {
+  /// All keys in this subtree are out of bound, continue with its neighbour leaf.
+  GoUp(NodeRef<BorrowType, K, V, InternalOrLeaf>),
+  Found(NodeRef<BorrowType, K, V, Leaf>),
+  GoDown(NodeRef<BorrowType, K, V, InternalOrLeaf>),
+}
+
+/// A range of leaves in key order, both ends inclusive.
+pub(crate) struct LeafRange<BorrowType, K, V> {
+  front: Option<NodeRef<BorrowType, K, V, Leaf>>,
+  back: Option<NodeRef<BorrowType, K, V, Leaf>>,
+}
+
+impl<'a, K, V> Clone for LeafRange<Immut<'a>, K, V> {
+  fn clone(&self) -> Self {
+    Self {
+      front: self.front,
+      back: self.back,
+    }
+  }
+}
+
+impl<BorrowType, K, V> LeafRange<BorrowType, K, V> {
+  pub(crate) fn none() -> Self {
+    Self {
+      front: None,
+      back: None,
+    }
+  }
+
+  /// Creates a range from `front` to `back`.
+  ///
+  /// `front` must not be after `back` in key order.
+  pub(crate) fn new(
+    front: NodeRef<BorrowType, K, V, Leaf>,
+    back: NodeRef<BorrowType, K, V, Leaf>,
+  ) -> Self {
+    Self {
+      front: Some(front),
+      back: Some(back),
+    }
+  }
+
+  /// Creates a range of all leaves in subtree rooted at `node`.
+  pub(crate) fn full(node: NodeRef<BorrowType, K, V, InternalOrLeaf>) -> Self {
+    let front = node.reborrow_raw().minimum_leaf();
+    let back = node.maximum_leaf();
+    Self::new(front, back)
+  }
+
+  /// Creates a range of all leaves in tree, which is empty when `root` is `None`.
+  pub(crate) fn from_root(root: Option<NodeRef<BorrowType, K, V, InternalOrLeaf>>) -> Self {
+    root.map_or_else(Self::none, Self::full)
+  }
+
+  /// Returns the first leaf and moves front forward.
+  pub(crate) fn next_checked(&mut self) -> Option<NodeRef<BorrowType, K, V, Leaf>> {
+    let front = self.front.take()?;
+    if self.back.as_ref().map(|b| b.get_inner()) == Some(front.get_inner()) {
+      self.back = None;
+    } else {
+      self.front = front.next_leaf();
+    }
+    Some(front)
+  }
+
+  /// Returns the last leaf and moves back backward.
+  pub(crate) fn next_back_checked(&mut self) -> Option<NodeRef<BorrowType, K, V, Leaf>> {
+    let back = self.back.take()?;
+    if self.front.as_ref().map(|f| f.get_inner()) == Some(back.get_inner()) {
+      self.front = None;
+    } else {
+      self.back = back.prev_leaf();
+    }
+    Some(back)
+  }
+}
+
+impl<BorrowType, K: ARTKey, V> LeafRange<BorrowType, K, V> {
+  /// Creates a range of leaves whose keys are in `start` and `end` bounds.
+  ///
+  /// # Panics
+  ///
+  /// If `start > end`, or if `start == end` and both bounds are `Excluded`.
+  pub(crate) fn range_search(
+    root: NodeRef<BorrowType, K, V, InternalOrLeaf>,
+    start: Bound<&[u8]>,
+    end: Bound<&[u8]>,
+  ) -> Self {
+    match (start, end) {
+      (Excluded(s), Excluded(e)) if s == e => {
+        panic!("range start and end are equal and excluded in ARTMap")
+      }
+      (Included(s) | Excluded(s), Included(e) | Excluded(e)) if s > e => {
+        panic!("range start is greater than range end in ARTMap")
+      }
+      _ => {}
+    }
+
+    let front = root.reborrow_raw().find_lower_bound_node(start);
+    let back = root.find_upper_bound_node(end);
+    match (front, back) {
+      (Some(front), Some(back)) if front.key_bytes().as_ref() <= back.key_bytes().as_ref() => Self::new(front, back),
+      _ => Self::none(),
+    }
+  }
+}
+
+impl<BorrowType, K: ARTKey, V> NodeRef<BorrowType, K, V, InternalOrLeaf> {
+  /// Find first leaf node whose key is in lower `bound`.
+  pub(crate) fn find_lower_bound_node(
+    self,
+    bound: Bound<&[u8]>,
+  ) -> Option<NodeRef<BorrowType, K, V, Leaf>> {
+    let (key, inclusive) = match bound {
+      Included(key) => (key, true),
+      Excluded(key) => (key, false),
+      Unbounded => return Some(self.minimum_leaf()),
+    };
+
+    let mut cur = self;
+    loop {
+      let result = match cur.downcast() {
+        NodeImpl::Internal(node) => node.search_lower_bound(key, inclusive),
+        NodeImpl::Leaf(leaf) => {
+          let ordering = leaf.key_bytes().as_ref().cmp(key);
+          match ordering {
+            Ordering::Greater => BoundSearchResult::Found(leaf),
+            Ordering::Equal if inclusive => BoundSearchResult::Found(leaf),
+            _ => BoundSearchResult::GoUp(leaf.forget_type()),
+          }
+        }
+      };
+
+      match result {
+        BoundSearchResult::GoUp(node) => return node.next_leaf(),
+        BoundSearchResult::Found(leaf) => return Some(leaf),
+        BoundSearchResult::GoDown(node) => cur = node,
+      }
+    }
+  }
+
+  /// Find last leaf node whose key is in upper `bound`.
+  pub(crate) fn find_upper_bound_node(
+    self,
+    bound: Bound<&[u8]>,
+  ) -> Option<NodeRef<BorrowType, K, V, Leaf>> {
+    let (key, inclusive) = match bound {
+      Included(key) => (key, true),
+      Excluded(key) => (key, false),
+      Unbounded => return Some(self.maximum_leaf()),
+    };
+
+    let mut cur = self;
+    loop {
+      let result = match cur.downcast() {
+        NodeImpl::Internal(node) => node.search_upper_bound(key, inclusive),
+        NodeImpl::Leaf(leaf) => {
+          let ordering = leaf.key_bytes().as_ref().cmp(key);
+          match ordering {
+            Ordering::Less => BoundSearchResult::Found(leaf),
+            Ordering::Equal if inclusive => BoundSearchResult::Found(leaf),
+            _ => BoundSearchResult::GoUp(leaf.forget_type()),
+          }
+        }
+      };
+
+      match result {
+        BoundSearchResult::GoUp(node) => return node.prev_leaf(),
+        BoundSearchResult::Found(leaf) => return Some(leaf),
+        BoundSearchResult::GoDown(node) => cur = node,
+      }
+    }
+  }
+}
+
+impl<BorrowType, K: ARTKey, V> NodeRef<BorrowType, K, V, Internal> {
+  /// Compares input key with partial key of this node.
+  ///
+  /// Returns ordering of common part and remaining input after partial key.
+  fn compare_partial_key<'k>(&self, key: &'k [u8]) -> (Ordering, Option<&'k [u8]>) {
+    let input_partial_key = &key[self.prefix_len()..];
+    self.with_partial_key(|this_partial_key| {
+      let len = this_partial_key.len().min(input_partial_key.len());
+      match input_partial_key[0..len].cmp(&this_partial_key[0..len]) {
+        Ordering::Equal if input_partial_key.len() < this_partial_key.len() => {
+          // Input key is a prefix of all keys in this subtree.
+          (Ordering::Less, None)
+        }
+        Ordering::Equal => (Ordering::Equal, Some(&input_partial_key[len..])),
+        ord => (ord, None),
+      }
+    })
+  }
+
+  fn search_lower_bound(self, key: &[u8], inclusive: bool) -> BoundSearchResult<BorrowType, K, V> {
+    match self.compare_partial_key(key) {
+      (Ordering::Less, _) => BoundSearchResult::Found(self.forget_type().minimum_leaf()),
+      (Ordering::Greater, _) => BoundSearchResult::GoUp(self.forget_type()),
+      (Ordering::Equal, rest) => match rest.and_then(|rest| rest.first()) {
+        None => {
+          // Input key ends at leaf of this node, and all children are greater.
+          if let (true, Some(leaf)) = (inclusive, self.get_leaf()) {
+            return BoundSearchResult::Found(leaf);
+          }
+          match self.next_child(0) {
+            Some((_, child)) => BoundSearchResult::Found(child.minimum_leaf()),
+            None => BoundSearchResult::GoUp(self.forget_type()),
+          }
+        }
+        Some(&k) => {
+          if let Some(child) = self.find_child(k) {
+            return BoundSearchResult::GoDown(child);
+          }
+          match k.checked_add(1).and_then(|k| self.next_child(k)) {
+            Some((_, child)) => BoundSearchResult::Found(child.minimum_leaf()),
+            None => BoundSearchResult::GoUp(self.forget_type()),
+          }
+        }
+      },
+    }
+  }
+
+  fn search_upper_bound(self, key: &[u8], inclusive: bool) -> BoundSearchResult<BorrowType, K, V> {
+    match self.compare_partial_key(key) {
+      (Ordering::Less, _) => BoundSearchResult::GoUp(self.forget_type()),
+      (Ordering::Greater, _) => BoundSearchResult::Found(self.forget_type().maximum_leaf()),
+      (Ordering::Equal, rest) => match rest.and_then(|rest| rest.first()) {
+        None => match (inclusive, self.get_leaf()) {
+          // Input key ends at leaf of this node, and all children are greater.
+          (true, Some(leaf)) => BoundSearchResult::Found(leaf),
+          _ => BoundSearchResult::GoUp(self.forget_type()),
+        },
+        Some(&k) => {
+          if let Some(child) = self.find_child(k) {
+            return BoundSearchResult::GoDown(child);
+          }
+          if let Some((_, child)) = k.checked_sub(1).and_then(|k| self.prev_child(k)) {
+            return BoundSearchResult::Found(child.maximum_leaf());
+          }
+          match self.get_leaf() {
+            Some(leaf) => BoundSearchResult::Found(leaf),
+            None => BoundSearchResult::GoUp(self.forget_type()),
+          }
+        }
+      },
+    }
+  }
+}
+
+impl<BorrowType, K, V> NodeRef<BorrowType, K, V, InternalOrLeaf> {
+  /// Returns the leaf with smallest key in this subtree.
+  pub(crate) fn minimum_leaf(self) -> NodeRef<BorrowType, K, V, Leaf> {
+    let mut cur = self;
+    loop {
+      match cur.downcast() {
+        NodeImpl::Leaf(leaf) => return leaf,
+        NodeImpl::Internal(internal) => {
+          // Leaf of internal node is a prefix of all keys in its children.
+          if let Some(leaf) = internal.get_leaf() {
+            return leaf;
+          }
+          cur = internal.next_child(0).expect("Internal node without entries!").1;
+        }
+      }
+    }
+  }
+
+  /// Returns the leaf with largest key in this subtree.
+  pub(crate) fn maximum_leaf(self) -> NodeRef<BorrowType, K, V, Leaf> {
+    let mut cur = self;
+    loop {
+      match cur.downcast() {
+        NodeImpl::Leaf(leaf) => return leaf,
+        NodeImpl::Internal(internal) => match internal.prev_child(u8::MAX) {
+          Some((_, child)) => cur = child,
+          None => return internal.get_leaf().expect("Internal node without entries!"),
+        },
+      }
+    }
+  }
+}
+
+impl<BorrowType, K, V, NodeType> NodeRef<BorrowType, K, V, NodeType> {
+  /// Returns the first leaf after all keys in this subtree, following parent pointers.
+  pub(crate) fn next_leaf(&self) -> Option<NodeRef<BorrowType, K, V, Leaf>> {
+    let mut cur = self.reborrow_raw();
+    loop {
+      let pos = cur.child_pos()?;
+      let parent = cur.ascend()?;
+      let next_k = match pos.to_idx() {
+        // Leaf slot comes before all children.
+        None => Some(0),
+        Some(k) => k.checked_add(1),
+      };
+      if let Some((_, child)) = next_k.and_then(|k| parent.next_child(k)) {
+        return Some(child.minimum_leaf());
+      }
+      cur = parent.forget_type();
+    }
+  }
+
+  /// Returns the last leaf before all keys in this subtree, following parent pointers.
+  pub(crate) fn prev_leaf(&self) -> Option<NodeRef<BorrowType, K, V, Leaf>> {
+    let mut cur = self.reborrow_raw();
+    loop {
+      let pos = cur.child_pos()?;
+      let parent = cur.ascend()?;
+      if let Some(k) = pos.to_idx() {
+        if let Some((_, child)) = k.checked_sub(1).and_then(|k| parent.prev_child(k)) {
+          return Some(child.maximum_leaf());
+        }
+        if let Some(leaf) = parent.get_leaf() {
+          return Some(leaf);
+        }
+      }
+      cur = parent.forget_type();
+    }
+  }
+
+  /// Duplicates this node ref without type information.
+  ///
+  /// Callers should make sure that the duplicated ref doesn't violate the borrow type.
+  fn reborrow_raw(&self) -> NodeRef<BorrowType, K, V, InternalOrLeaf> {
+    NodeRef::from_raw_parts(self.get_inner(), self.prefix_len())
+  }
+}
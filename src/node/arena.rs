@@ -0,0 +1,231 @@
+//! Slab allocation of nodes.
+//!
+//! Each map owns an arena with one slab per node class. Slots are carved from chunks in order, and
+//! freed slots are linked into a free list for reuse. Chunks are allocated from the allocator of
+//! the map, and only released when the arena is dropped or released, so freeing a whole tree
+//! doesn't touch its nodes.
+
+use std::alloc::{handle_alloc_error, Layout};
+use std::mem;
+use std::ptr::NonNull;
+
+use allocator_api2::alloc::{Allocator, Global};
+
+use crate::node::node16::Node16Children;
+use crate::node::node256::Node256Children;
+use crate::node::node4::Node4Children;
+use crate::node::node48::Node48Children;
+use crate::node::{InternalNode, LeafNode};
+
+/// Number of slots of the first chunk of a slab.
+const MIN_CHUNK_LEN: usize = 4;
+/// Chunks grow geometrically until they reach this size.
+const MAX_CHUNK_BYTES: usize = 64 * 1024;
+
+pub(crate) struct NodeArena<K, V, A: Allocator = Global> {
+  slabs: Slabs<K, V>,
+  alloc: A,
+}
+
+/// Slabs of all node classes, kept apart from the allocator so both can be borrowed at once.
+pub(crate) struct Slabs<K, V> {
+  leaves: Slab<LeafNode<K, V>>,
+  node4: Slab<InternalNode<Node4Children<K, V>, K, V>>,
+  node16: Slab<InternalNode<Node16Children<K, V>, K, V>>,
+  node48: Slab<InternalNode<Node48Children<K, V>, K, V>>,
+  node256: Slab<InternalNode<Node256Children<K, V>, K, V>>,
+}
+
+/// Slots of one node class.
+pub(crate) struct Slab<T> {
+  /// Allocated chunks with their lengths.
+  chunks: Vec<(NonNull<T>, usize)>,
+  /// Number of slots carved from the last chunk.
+  used: usize,
+  /// Head of freed slots, each of which stores pointer to the next one in its first word.
+  free: Option<NonNull<T>>,
+}
+
+/// Node type allocated from a slab of `NodeArena`.
+pub(crate) trait SlabNode<K, V>: Sized {
+  fn slab(slabs: &mut Slabs<K, V>) -> &mut Slab<Self>;
+}
+
+impl<K, V, A: Allocator> NodeArena<K, V, A> {
+  pub(crate) fn new_in(alloc: A) -> Self {
+    Self {
+      slabs: Slabs {
+        leaves: Slab::new(),
+        node4: Slab::new(),
+        node16: Slab::new(),
+        node48: Slab::new(),
+        node256: Slab::new(),
+      },
+      alloc,
+    }
+  }
+
+  pub(crate) fn allocator(&self) -> &A {
+    &self.alloc
+  }
+
+  /// Moves `node` into a free slot of its class.
+  pub(crate) fn alloc<T: SlabNode<K, V>>(&mut self, node: T) -> NonNull<T> {
+    T::slab(&mut self.slabs).alloc(&self.alloc, node)
+  }
+
+  /// Puts slot of `ptr` back to free list, without dropping it.
+  ///
+  /// # Safety
+  ///
+  /// `ptr` must be allocated from this arena, and is not used afterwards.
+  pub(crate) unsafe fn free<T: SlabNode<K, V>>(&mut self, ptr: NonNull<T>) {
+    T::slab(&mut self.slabs).free(ptr)
+  }
+
+  /// Returns all chunks to the allocator, without dropping nodes in them.
+  ///
+  /// # Safety
+  ///
+  /// No node allocated from this arena is used afterwards.
+  pub(crate) unsafe fn release(&mut self) {
+    let Slabs {
+      leaves,
+      node4,
+      node16,
+      node48,
+      node256,
+    } = &mut self.slabs;
+    leaves.release(&self.alloc);
+    node4.release(&self.alloc);
+    node16.release(&self.alloc);
+    node48.release(&self.alloc);
+    node256.release(&self.alloc);
+  }
+
+  /// Returns bytes of all chunks, including free slots.
+  pub(crate) fn reserved_bytes(&self) -> usize {
+    let slabs = &self.slabs;
+    slabs.leaves.reserved_bytes()
+      + slabs.node4.reserved_bytes()
+      + slabs.node16.reserved_bytes()
+      + slabs.node48.reserved_bytes()
+      + slabs.node256.reserved_bytes()
+  }
+}
+
+impl<K, V> NodeArena<K, V> {
+  pub(crate) fn new() -> Self {
+    Self::new_in(Global)
+  }
+}
+
+impl<K, V> Default for NodeArena<K, V> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<K, V, A: Allocator> Drop for NodeArena<K, V, A> {
+  fn drop(&mut self) {
+    // SAFETY: Nodes can't outlive the arena.
+    unsafe { self.release() }
+  }
+}
+
+impl<T> Slab<T> {
+  /// Freed slots hold a link, so they must fit one.
+  const SLOT_HOLDS_LINK: () = assert!(
+    mem::size_of::<T>() >= mem::size_of::<Option<NonNull<T>>>()
+      && mem::align_of::<T>() >= mem::align_of::<Option<NonNull<T>>>()
+  );
+
+  fn new() -> Self {
+    let () = Self::SLOT_HOLDS_LINK;
+    Self {
+      chunks: Vec::new(),
+      used: 0,
+      free: None,
+    }
+  }
+
+  fn alloc<A: Allocator>(&mut self, alloc: &A, value: T) -> NonNull<T> {
+    let ptr = match self.free {
+      Some(ptr) => {
+        // SAFETY: Free slots hold a link to the next one.
+        self.free = unsafe { ptr.cast::<Option<NonNull<T>>>().as_ptr().read() };
+        ptr
+      }
+      None => self.carve(alloc),
+    };
+    // SAFETY: The slot is unused.
+    unsafe { ptr.as_ptr().write(value) };
+    ptr
+  }
+
+  unsafe fn free(&mut self, ptr: NonNull<T>) {
+    ptr.cast::<Option<NonNull<T>>>().as_ptr().write(self.free);
+    self.free = Some(ptr);
+  }
+
+  /// Takes the next slot never used, allocating a new chunk if the last one is full.
+  fn carve<A: Allocator>(&mut self, alloc: &A) -> NonNull<T> {
+    let chunk = match self.chunks.last() {
+      Some(&(chunk, len)) if self.used < len => chunk,
+      last => {
+        let max_len = (MAX_CHUNK_BYTES / mem::size_of::<T>()).max(MIN_CHUNK_LEN);
+        let len = last.map_or(MIN_CHUNK_LEN, |&(_, len)| (len * 2).min(max_len));
+        let layout = Layout::array::<T>(len).expect("Slab chunk is too large!");
+        let chunk = match alloc.allocate(layout) {
+          Ok(chunk) => chunk.cast(),
+          Err(_) => handle_alloc_error(layout),
+        };
+        self.chunks.push((chunk, len));
+        self.used = 0;
+        chunk
+      }
+    };
+    // SAFETY: `used` is in bounds of the chunk.
+    let ptr = unsafe { NonNull::new_unchecked(chunk.as_ptr().add(self.used)) };
+    self.used += 1;
+    ptr
+  }
+
+  fn reserved_bytes(&self) -> usize {
+    self
+      .chunks
+      .iter()
+      .map(|&(_, len)| len * mem::size_of::<T>())
+      .sum()
+  }
+
+  /// Deallocates all chunks, and starts over with no slots.
+  unsafe fn release<A: Allocator>(&mut self, alloc: &A) {
+    for (chunk, len) in self.chunks.drain(..) {
+      // Chunk was allocated from `alloc` with the same layout.
+      alloc.deallocate(chunk.cast(), Layout::array::<T>(len).unwrap());
+    }
+    self.used = 0;
+    self.free = None;
+  }
+}
+
+macro_rules! impl_slab_node {
+  ($($t:ty => $slab:ident),*) => {
+    $(
+      impl<K, V> SlabNode<K, V> for $t {
+        fn slab(slabs: &mut Slabs<K, V>) -> &mut Slab<Self> {
+          &mut slabs.$slab
+        }
+      }
+    )*
+  };
+}
+
+impl_slab_node!(
+  LeafNode<K, V> => leaves,
+  InternalNode<Node4Children<K, V>, K, V> => node4,
+  InternalNode<Node16Children<K, V>, K, V> => node16,
+  InternalNode<Node48Children<K, V>, K, V> => node48,
+  InternalNode<Node256Children<K, V>, K, V> => node256
+);
@@ -1,34 +1,38 @@
+use allocator_api2::alloc::Allocator;
+
+use crate::key::ARTKey;
 use crate::marker::{Internal, InternalOrLeaf, Leaf, Mut};
 use crate::node::node16::Node16Children;
 use crate::node::node256::Node256Children;
 use crate::node::node4::Node4Children;
 use crate::node::node48::Node48Children;
 
+use crate::node::arena::SlabNode;
+use crate::node::{BoxedNode, Handle, LeafNode, NodeArena, NodeBase, NodeType};
 use crate::node::{ChildPos, NodeRef};
-use crate::node::PartialKey::FixSized;
-use crate::node::{BoxedNode, LeafNode, NodeBase, NodeType};
 use std::marker::PhantomData;
-use std::mem::swap;
+use std::mem;
+use std::ptr;
 use std::ptr::NonNull;
 
 const MAX_PREFIX_LEN: usize = 16;
 
-#[derive(Default)]
-pub(crate) struct Fixed {
-  partial_prefix: [u8; MAX_PREFIX_LEN],
-  partial_prefix_len: usize,
-}
-
-pub(crate) enum PartialKey {
-  FixSized(Fixed),
-  VarSized(Vec<u8>),
+/// Partial key of an internal node.
+///
+/// Only first `MAX_PREFIX_LEN` bytes are stored, along with the full length. The rest are skipped
+/// when searching, and read from a leaf below when needed, since all keys below share them.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct PartialKey {
+  prefix: [u8; MAX_PREFIX_LEN],
+  len: usize,
 }
 
+#[repr(C)]
 pub(crate) struct InternalNodeBase<K, V> {
   node_base: NodeBase<K, V>,
   partial_key: PartialKey,
   leaf: Option<NonNull<LeafNode<K, V>>>,
-  children_count: u8,
+  children_count: u16,
 }
 
 #[repr(C)]
@@ -39,22 +43,28 @@ pub(crate) struct InternalNode<C, K, V> {
 
 pub(crate) trait Children<K, V>: Default {
   const NODE_TYPE: NodeType;
+  const CAPACITY: usize;
 
+  /// Set child with key `k`, and returns previous child with same key.
+  ///
+  /// # Safety
+  ///
+  /// When there is no child with key `k`, the container must not be full.
   unsafe fn set_child(&mut self, k: u8, node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>>;
-  unsafe fn set_child_at(&mut self, idx: usize, node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>>;
-  fn child_at(&self, idx: usize) -> Option<BoxedNode<K, V>>;
-}
-
-impl Fixed {
-  #[inline(always)]
-  fn partial_prefix(&self) -> &[u8] {
-    &self.partial_prefix[0..self.partial_prefix_len]
-  }
-
-  fn set_data(&mut self, new_data: &[u8]) {
-    assert!(new_data.len() <= MAX_PREFIX_LEN);
-    (&mut self.partial_prefix[0..new_data.len()]).copy_from_slice(new_data);
-    self.partial_prefix_len = new_data.len();
+  fn remove_child(&mut self, k: u8) -> Option<BoxedNode<K, V>>;
+  fn find_child(&self, k: u8) -> Option<BoxedNode<K, V>>;
+  /// Returns first child whose key is not less than `k`.
+  fn next_child(&self, k: u8) -> Option<(u8, BoxedNode<K, V>)>;
+  /// Returns last child whose key is not greater than `k`.
+  fn prev_child(&self, k: u8) -> Option<(u8, BoxedNode<K, V>)>;
+
+  /// Visit all children in ascending key order.
+  fn for_each_child<F: FnMut(u8, BoxedNode<K, V>)>(&self, mut f: F) {
+    let mut next = self.next_child(0);
+    while let Some((k, child)) = next {
+      f(k, child);
+      next = k.checked_add(1).and_then(|k| self.next_child(k));
+    }
   }
 }
 
@@ -63,53 +73,71 @@ pub(crate) type InternalNode16<K, V> = InternalNode<Node16Children<K, V>, K, V>;
 pub(crate) type InternalNode48<K, V> = InternalNode<Node48Children<K, V>, K, V>;
 pub(crate) type InternalNode256<K, V> = InternalNode<Node256Children<K, V>, K, V>;
 
+/// Runs `$code` with `$node` bound to the pointer to this node of its concrete class, or to the
+/// reference returned by `$as` on that pointer.
 macro_rules! with_internal_node {
-  ($self: expr, $node: ident, $code: block) => {
-      match $self.as_base_ref().node_type {
-        NodeType::Node4 => {
-          let $node = unsafe { $self.inner.cast::<InternalNode4<K, V>>().as_mut() };
-          $code
-        }
-        NodeType::Node16 => {
-          let $node = unsafe { $self.inner.cast::<InternalNode16<K, V>>().as_mut() };
-          $code
-        }
-        NodeType::Node48 => {
-          let $node = unsafe { $self.inner.cast::<InternalNode48<K, V>>().as_mut() };
-          $code
-        }
-        NodeType::Node256 => {
-          let $node = unsafe { $self.inner.cast::<InternalNode256<K, V>>().as_mut() };
-          $code
-        }
-        NodeType::Leaf => panic!("This should not happen!"),
+  ($self: expr, $node: ident, $($as: ident,)? $code: block) => {
+    match $self.as_base_ref().node_type {
+      NodeType::Node4 => {
+        let $node = $self.inner.cast::<InternalNode4<K, V>>();
+        $(let $node = unsafe { $node.$as() };)?
+        $code
       }
-  }
-}
-
-pub(crate) enum InternalNodeImpl<'a, K, V> {
-  Node4(&'a InternalNode4<K, V>),
-  Node16(&'a InternalNode16<K, V>),
-  Node48(&'a InternalNode48<K, V>),
-  Node256(&'a InternalNode256<K, V>),
+      NodeType::Node16 => {
+        let $node = $self.inner.cast::<InternalNode16<K, V>>();
+        $(let $node = unsafe { $node.$as() };)?
+        $code
+      }
+      NodeType::Node48 => {
+        let $node = $self.inner.cast::<InternalNode48<K, V>>();
+        $(let $node = unsafe { $node.$as() };)?
+        $code
+      }
+      NodeType::Node256 => {
+        let $node = $self.inner.cast::<InternalNode256<K, V>>();
+        $(let $node = unsafe { $node.$as() };)?
+        $code
+      }
+      NodeType::Leaf => panic!("This should not happen!"),
+    }
+  };
 }
 
 impl<K, V> InternalNodeBase<K, V> {
-  pub(crate) fn partial_key(&self) -> &[u8] {
-    self.partial_key.as_slice()
+  pub(crate) fn node_base(&self) -> &NodeBase<K, V> {
+    &self.node_base
+  }
+
+  /// Returns full length of partial key.
+  pub(crate) fn partial_key_len(&self) -> usize {
+    self.partial_key.len
   }
 
   pub(crate) fn set_partial_key(&mut self, partial_key: &[u8]) {
-    self.partial_key.update(partial_key);
+    self.partial_key = PartialKey::new(partial_key);
   }
 
+  /// Prepend `prefix` and `k` to partial key, used when merging this node into its parent.
+  pub(crate) fn prepend_partial_key(&mut self, prefix: &PartialKey, k: u8) {
+    self.partial_key.prepend(prefix, k);
+  }
+
+  /// Set leaf node of node at `this`, and returns previous one. The leaf keeps `this` as its
+  /// parent pointer, so `this` must be derived from the pointer to the node allocation rather
+  /// than from a reference, which is invalidated by later writes to the node.
+  ///
+  /// # Safety
+  ///
+  /// This method accepts a raw pointer and owns it afterwards.
   pub(crate) unsafe fn set_leaf(
-    &mut self,
+    this: NonNull<Self>,
     leaf_node: NonNull<LeafNode<K, V>>,
   ) -> Option<NonNull<LeafNode<K, V>>> {
-    let mut ret = Some(leaf_node.cast());
-    swap(&mut self.leaf, &mut ret);
-    ret
+    leaf_node
+      .cast::<NodeBase<K, V>>()
+      .as_mut()
+      .set_parent(this, ChildPos::from(None));
+    (*this.as_ptr()).leaf.replace(leaf_node)
   }
 
   pub(crate) fn get_leaf(&self) -> Option<NonNull<LeafNode<K, V>>> {
@@ -118,111 +146,165 @@ impl<K, V> InternalNodeBase<K, V> {
 }
 
 impl<K, V, C: Children<K, V>> InternalNode<C, K, V> {
-  pub(crate) unsafe fn new_root() -> Box<Self> {
-    Box::new(Self {
+  /// Creates an empty node without parent.
+  pub(crate) fn new() -> Self {
+    Self {
       base: InternalNodeBase {
-        node_base: NodeBase::new_root(C::NODE_TYPE),
+        node_base: NodeBase::new(C::NODE_TYPE),
         partial_key: PartialKey::default(),
         leaf: None,
         children_count: 0,
       },
       children: C::default(),
-    })
+    }
   }
 
-  pub(crate) unsafe fn new(parent: NonNull<InternalNodeBase<K, V>>, child_pos: ChildPos) -> Box<Self> {
-    Box::new(Self {
-      base: InternalNodeBase {
-        node_base: NodeBase::new(C::NODE_TYPE, parent, child_pos),
-        partial_key: PartialKey::default(),
-        leaf: None,
-        children_count: 0,
-      },
-      children: C::default(),
-    })
+  pub(crate) fn base_mut(&mut self) -> &mut InternalNodeBase<K, V> {
+    &mut self.base
   }
 
-  pub(crate) fn base(&self) -> &InternalNodeBase<K, V> {
-    &self.base
+  fn is_full(&self) -> bool {
+    self.base.children_count as usize >= C::CAPACITY
   }
 
-  pub(crate) fn base_mut(&mut self) -> &mut InternalNodeBase<K, V> {
-    &mut self.base
+  /// Returns pointer to base of node at `this`, with the same provenance.
+  unsafe fn base_ptr(this: NonNull<Self>) -> NonNull<InternalNodeBase<K, V>> {
+    NonNull::new_unchecked(ptr::addr_of_mut!((*this.as_ptr()).base))
   }
 
-  /// Insert node with k and return previous node pointer.
+  /// Insert node with k into node at `this` and return previous node pointer. Same as
+  /// [`InternalNodeBase::set_leaf`], the parent pointer of child is derived from `this`.
   ///
   /// # Safety
   ///
   /// This method accepts a raw pointer and owns it afterwards. If a child node with same key
   /// already exists, it's returned and the caller has its ownership.
   pub(crate) unsafe fn set_child(
-    &mut self,
+    this: NonNull<Self>,
     k: u8,
-    node_ptr: BoxedNode<K, V>,
+    mut node_ptr: BoxedNode<K, V>,
   ) -> Option<BoxedNode<K, V>> {
-    self.children.set_child(k, node_ptr)
-  }
-
-  pub(crate) unsafe fn set_child_at(&mut self, pos: ChildPos, node_ptr: Option<BoxedNode<K, V>>)
-                                    -> Option<BoxedNode<K, V>> {
-    todo!()
-  }
-}
-
-impl PartialKey {
-  fn as_slice(&self) -> &[u8] {
-    match self {
-      PartialKey::FixSized(prefix) => prefix.partial_prefix(),
-      PartialKey::VarSized(key) => key.as_slice(),
+    node_ptr
+      .as_mut()
+      .set_parent(Self::base_ptr(this), ChildPos::from(Some(k)));
+    let this = this.as_ptr();
+    let ret = (*this).children.set_child(k, node_ptr);
+    if ret.is_none() {
+      (*this).base.children_count += 1;
     }
+    ret
   }
 
-  pub(crate) fn len(&self) -> usize {
-    self.as_slice().len()
+  /// Set leaf node of node at `this`, and returns previous one.
+  ///
+  /// # Safety
+  ///
+  /// Same as [`InternalNodeBase::set_leaf`].
+  pub(crate) unsafe fn set_leaf(
+    this: NonNull<Self>,
+    leaf_node: NonNull<LeafNode<K, V>>,
+  ) -> Option<NonNull<LeafNode<K, V>>> {
+    InternalNodeBase::set_leaf(Self::base_ptr(this), leaf_node)
   }
 
-  fn update(&mut self, new_partial_key: &[u8]) {
-    match self {
-      PartialKey::FixSized(cur_key) => {
-        if new_partial_key.len() > MAX_PREFIX_LEN {
-          *self = PartialKey::VarSized(Vec::from(new_partial_key));
-        } else {
-          cur_key.update(new_partial_key);
-        }
-      }
-      PartialKey::VarSized(cur_key) => {
-        if new_partial_key.len() > MAX_PREFIX_LEN {
-          cur_key.copy_from_slice(new_partial_key);
-        } else {
-          let fixed_key = FixSized(Fixed::new(new_partial_key));
-          *self = fixed_key;
+  /// Set or remove node at `pos`, and return previous node pointer.
+  ///
+  /// # Safety
+  ///
+  /// Same as [`set_child`](Self::set_child).
+  pub(crate) unsafe fn set_child_at(
+    this: NonNull<Self>,
+    pos: ChildPos,
+    node_ptr: Option<BoxedNode<K, V>>,
+  ) -> Option<BoxedNode<K, V>> {
+    match (pos.to_idx(), node_ptr) {
+      (Some(k), Some(ptr)) => Self::set_child(this, k, ptr),
+      (Some(k), None) => {
+        let this = this.as_ptr();
+        let ret = (*this).children.remove_child(k);
+        if ret.is_some() {
+          (*this).base.children_count -= 1;
         }
+        ret
       }
+      (None, Some(ptr)) => Self::set_leaf(this, ptr.cast()).map(NonNull::cast),
+      (None, None) => (*this.as_ptr()).base.leaf.take().map(NonNull::cast),
     }
   }
-}
 
-impl Default for PartialKey {
-  fn default() -> Self {
-    PartialKey::FixSized(Fixed::default())
+  /// Moves everything in this node into a newly allocated node with children type `D`, and
+  /// frees this node.
+  ///
+  /// Parent pointers of children and leaf are updated, while holder of this node is not.
+  unsafe fn change_type<D: Children<K, V>, A: Allocator>(
+    this: NonNull<Self>,
+    arena: &mut NodeArena<K, V, A>,
+  ) -> NonNull<InternalNode<D, K, V>>
+  where
+    Self: SlabNode<K, V>,
+    InternalNode<D, K, V>: SlabNode<K, V>,
+  {
+    let InternalNode { base, children } = this.as_ptr().read();
+    arena.free(this);
+    let new_node = arena.alloc(InternalNode {
+      base: InternalNodeBase {
+        node_base: NodeBase {
+          node_type: D::NODE_TYPE,
+          ..base.node_base
+        },
+        partial_key: base.partial_key,
+        leaf: None,
+        children_count: 0,
+      },
+      children: D::default(),
+    });
+
+    children.for_each_child(|k, child| {
+      InternalNode::set_child(new_node, k, child);
+    });
+    if let Some(leaf) = base.leaf {
+      InternalNode::set_leaf(new_node, leaf);
+    }
+
+    new_node
   }
 }
 
-impl Fixed {
-  fn new(slice: &[u8]) -> Self {
-    debug_assert!(slice.len() < MAX_PREFIX_LEN);
+impl PartialKey {
+  pub(crate) fn new(partial_key: &[u8]) -> Self {
+    let mut ret = Self {
+      prefix: [0; MAX_PREFIX_LEN],
+      len: partial_key.len(),
+    };
+    let stored_len = partial_key.len().min(MAX_PREFIX_LEN);
+    ret.prefix[0..stored_len].copy_from_slice(&partial_key[0..stored_len]);
+    ret
+  }
 
-    let mut ret = Self::default();
-    ret.update(slice);
+  /// Returns stored bytes, which is the whole partial key unless it's truncated.
+  #[inline(always)]
+  fn stored(&self) -> &[u8] {
+    &self.prefix[0..self.len.min(MAX_PREFIX_LEN)]
+  }
 
-    ret
+  #[inline(always)]
+  fn is_truncated(&self) -> bool {
+    self.len > MAX_PREFIX_LEN
   }
 
-  fn update(&mut self, slice: &[u8]) {
-    debug_assert!(slice.len() < MAX_PREFIX_LEN);
-    self.partial_prefix[0..slice.len()].copy_from_slice(slice);
-    self.partial_prefix_len = slice.len();
+  /// Prepend `prefix` and `k`. Stored bytes of both keys are enough to fill the new one, since
+  /// `prefix` is entirely stored unless it fills the storage itself.
+  fn prepend(&mut self, prefix: &PartialKey, k: u8) {
+    let mut new_prefix = prefix.prefix;
+    let mut stored_len = prefix.stored().len();
+    if stored_len < MAX_PREFIX_LEN {
+      new_prefix[stored_len] = k;
+      stored_len += 1;
+      let rest = (MAX_PREFIX_LEN - stored_len).min(self.stored().len());
+      new_prefix[stored_len..stored_len + rest].copy_from_slice(&self.prefix[0..rest]);
+    }
+    self.prefix = new_prefix;
+    self.len += prefix.len + 1;
   }
 }
 
@@ -239,34 +321,55 @@ impl<BorrowType, K, V> NodeRef<BorrowType, K, V, Internal> {
     unsafe { self.inner.cast().as_mut() }
   }
 
-  fn as_internal_impl(&self) -> InternalNodeImpl<'_, K, V> {
-    // SAFETY: This is internal node.
-    unsafe {
-      match self.as_base_ref().node_type {
-        NodeType::Node4 => {
-          InternalNodeImpl::Node4(self.inner.cast::<InternalNode4<K, V>>().as_ref())
-        }
-        NodeType::Node16 => {
-          InternalNodeImpl::Node16(self.inner.cast::<InternalNode16<K, V>>().as_ref())
-        }
-        NodeType::Node48 => {
-          InternalNodeImpl::Node48(self.inner.cast::<InternalNode48<K, V>>().as_ref())
-        }
-        NodeType::Node256 => {
-          InternalNodeImpl::Node256(self.inner.cast::<InternalNode256<K, V>>().as_ref())
-        }
-        NodeType::Leaf => panic!("This should not happen!"),
-      }
+  /// Prefix length of children of this node, excluding the key byte in this node.
+  fn children_prefix_len(&self) -> usize {
+    self.prefix_len + self.partial_key_len()
+  }
+
+  fn child_ref(&self, ptr: BoxedNode<K, V>) -> NodeRef<BorrowType, K, V, InternalOrLeaf> {
+    NodeRef {
+      inner: ptr,
+      prefix_len: self.children_prefix_len() + 1,
+      _marker: PhantomData,
     }
   }
 
-  pub(crate) fn find_child(&self, _k: u8) -> Option<NodeRef<BorrowType, K, V, InternalOrLeaf>> {
-    todo!()
+  pub(crate) fn children_count(&self) -> usize {
+    self.as_internal_ref().children_count as usize
+  }
+
+  /// Returns first child whose key is not less than `k`.
+  pub(crate) fn next_child(&self, k: u8) -> Option<(u8, NodeRef<BorrowType, K, V, InternalOrLeaf>)> {
+    let child = with_internal_node!(self, node, as_ref, { node.children.next_child(k) });
+    child.map(|(k, ptr)| (k, self.child_ref(ptr)))
+  }
+
+  /// Visits all children in ascending key order.
+  pub(crate) fn for_each_child<F: FnMut(u8, NodeRef<BorrowType, K, V, InternalOrLeaf>)>(
+    &self,
+    mut f: F,
+  ) {
+    with_internal_node!(self, node, as_ref, {
+      node
+        .children
+        .for_each_child(|k, ptr| f(k, self.child_ref(ptr)))
+    })
+  }
+
+  /// Returns last child whose key is not greater than `k`.
+  pub(crate) fn prev_child(&self, k: u8) -> Option<(u8, NodeRef<BorrowType, K, V, InternalOrLeaf>)> {
+    let child = with_internal_node!(self, node, as_ref, { node.children.prev_child(k) });
+    child.map(|(k, ptr)| (k, self.child_ref(ptr)))
+  }
+
+  pub(crate) fn find_child(&self, k: u8) -> Option<NodeRef<BorrowType, K, V, InternalOrLeaf>> {
+    let child = with_internal_node!(self, node, as_ref, { node.children.find_child(k) });
+    child.map(|ptr| self.child_ref(ptr))
   }
 
   pub(crate) fn get_leaf(&self) -> Option<NodeRef<BorrowType, K, V, Leaf>> {
     let internal_ref = self.as_internal_ref();
-    let leaf_prefix_len = self.prefix_len + internal_ref.partial_key().len();
+    let leaf_prefix_len = self.children_prefix_len();
     internal_ref.get_leaf().map(|leaf_ptr| NodeRef {
       inner: leaf_ptr.cast(),
       prefix_len: leaf_prefix_len,
@@ -274,49 +377,250 @@ impl<BorrowType, K, V> NodeRef<BorrowType, K, V, Internal> {
     })
   }
 
-  // pub(crate) fn child_at(&self, idx: usize) -> Option<NodeRef<BorrowType, K, V, InternalOrLeaf>> {
-  //   let internal_ref = self.as_internal_impl();
-  //   if let Some(child_ptr) = internal_ref.child_at(idx) {
-  //     let child_prefix_len = self.prefix_len + self.as_internal_ref().partial_key.len() + 1;
-  //     Some(NodeRef {
-  //       inner: child_ptr,
-  //       prefix_len: child_prefix_len,
-  //       _marker: PhantomData,
-  //     })
-  //   } else {
-  //     None
-  //   }
-  // }
+  /// Returns full length of partial key.
+  pub(crate) fn partial_key_len(&self) -> usize {
+    self.as_internal_ref().partial_key_len()
+  }
+
+  /// Returns stored bytes of partial key, which are first `MAX_PREFIX_LEN` bytes at most.
+  pub(crate) fn stored_partial_key(&self) -> &[u8] {
+    self.as_internal_ref().partial_key.stored()
+  }
+
+  /// Returns `true` if only a part of partial key is stored.
+  pub(crate) fn is_partial_key_truncated(&self) -> bool {
+    self.as_internal_ref().partial_key.is_truncated()
+  }
+
+  pub(crate) fn partial_key(&self) -> &PartialKey {
+    &self.as_internal_ref().partial_key
+  }
+
+  pub(crate) fn node_type(&self) -> &NodeType {
+    &self.as_base_ref().node_type
+  }
+
+  /// Returns bounds of children count of this node class. A node has more children than the next
+  /// smaller class holds, otherwise it would have been shrunk.
+  #[cfg(feature = "debug-invariants")]
+  pub(crate) fn children_count_bounds(&self) -> (usize, usize) {
+    match self.as_base_ref().node_type {
+      NodeType::Node4 => (1, Node4Children::<K, V>::CAPACITY),
+      NodeType::Node16 => (
+        Node4Children::<K, V>::CAPACITY + 1,
+        Node16Children::<K, V>::CAPACITY,
+      ),
+      NodeType::Node48 => (
+        Node16Children::<K, V>::CAPACITY + 1,
+        Node48Children::<K, V>::CAPACITY,
+      ),
+      NodeType::Node256 => (
+        Node48Children::<K, V>::CAPACITY + 1,
+        Node256Children::<K, V>::CAPACITY,
+      ),
+      NodeType::Leaf => unreachable!("This should not happen!"),
+    }
+  }
+
+  /// Returns allocated size of this node.
+  pub(crate) fn node_size(&self) -> usize {
+    with_internal_node!(self, node, as_ref, { mem::size_of_val(node) })
+  }
+
+  /// Frees this node without touching its children and leaf.
+  ///
+  /// # Safety
+  ///
+  /// This node must have been detached from tree, and its children and leaf are owned by others.
+  /// `arena` must be the one this node is allocated from.
+  pub(crate) unsafe fn deallocate<A: Allocator>(self, arena: &mut NodeArena<K, V, A>) {
+    with_internal_node!(self, node, {
+      arena.free(node);
+    })
+  }
+}
+
+impl<BorrowType, K: ARTKey, V> NodeRef<BorrowType, K, V, Internal> {
+  /// Calls `f` with the full partial key. When it's truncated, the key is read from a leaf below,
+  /// since all keys in this subtree share it.
+  pub(crate) fn with_partial_key<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+    if !self.is_partial_key_truncated() {
+      return f(self.stored_partial_key());
+    }
+    let leaf =
+      NodeRef::<BorrowType, K, V, InternalOrLeaf>::from_raw_parts(self.inner, self.prefix_len)
+        .minimum_leaf();
+    let key = leaf.key_bytes();
+    f(&key.as_ref()[self.prefix_len..self.children_prefix_len()])
+  }
+}
 
-  pub(crate) fn partial_key(&self) -> &[u8] {
-    self.as_internal_ref().partial_key()
+impl<BorrowType, K, V> NodeRef<BorrowType, K, V, Internal> {
+  /// Allocates a node without parent and entries, which has same class and partial key as this
+  /// node.
+  pub(crate) fn clone_empty<A: Allocator>(
+    &self,
+    arena: &mut NodeArena<K, V, A>,
+  ) -> BoxedNode<K, V> {
+    fn new_node<C: Children<K, V>, K, V, A: Allocator>(
+      partial_key: PartialKey,
+      arena: &mut NodeArena<K, V, A>,
+    ) -> BoxedNode<K, V>
+    where
+      InternalNode<C, K, V>: SlabNode<K, V>,
+    {
+      let mut node = InternalNode::<C, K, V>::new();
+      node.base_mut().partial_key = partial_key;
+      arena.alloc(node).cast()
+    }
+
+    let partial_key = *self.partial_key();
+    match self.as_base_ref().node_type {
+      NodeType::Node4 => new_node::<Node4Children<K, V>, K, V, A>(partial_key, arena),
+      NodeType::Node16 => new_node::<Node16Children<K, V>, K, V, A>(partial_key, arena),
+      NodeType::Node48 => new_node::<Node48Children<K, V>, K, V, A>(partial_key, arena),
+      NodeType::Node256 => new_node::<Node256Children<K, V>, K, V, A>(partial_key, arena),
+      NodeType::Leaf => unreachable!("This should not happen!"),
+    }
+  }
+}
+
+/// Allocates a node without parent, whose class is the smallest one holding all `children`.
+///
+/// # Safety
+///
+/// This function owns `leaf` and `children` afterwards. Keys of `children` must be distinct.
+pub(crate) unsafe fn new_internal_node<K, V, I, A: Allocator>(
+  partial_key: &[u8],
+  leaf: Option<NonNull<LeafNode<K, V>>>,
+  children: I,
+  arena: &mut NodeArena<K, V, A>,
+) -> BoxedNode<K, V>
+where
+  I: ExactSizeIterator<Item = (u8, BoxedNode<K, V>)>,
+{
+  unsafe fn new_node<C: Children<K, V>, K, V, A: Allocator>(
+    partial_key: &[u8],
+    leaf: Option<NonNull<LeafNode<K, V>>>,
+    children: impl Iterator<Item = (u8, BoxedNode<K, V>)>,
+    arena: &mut NodeArena<K, V, A>,
+  ) -> BoxedNode<K, V>
+  where
+    InternalNode<C, K, V>: SlabNode<K, V>,
+  {
+    let mut node_ptr = arena.alloc(InternalNode::<C, K, V>::new());
+    node_ptr.as_mut().base_mut().set_partial_key(partial_key);
+    if let Some(leaf) = leaf {
+      InternalNode::set_leaf(node_ptr, leaf);
+    }
+    for (k, child) in children {
+      InternalNode::set_child(node_ptr, k, child);
+    }
+    node_ptr.cast()
+  }
+
+  let len = children.len();
+  if len <= Node4Children::<K, V>::CAPACITY {
+    new_node::<Node4Children<K, V>, K, V, A>(partial_key, leaf, children, arena)
+  } else if len <= Node16Children::<K, V>::CAPACITY {
+    new_node::<Node16Children<K, V>, K, V, A>(partial_key, leaf, children, arena)
+  } else if len <= Node48Children::<K, V>::CAPACITY {
+    new_node::<Node48Children<K, V>, K, V, A>(partial_key, leaf, children, arena)
+  } else {
+    new_node::<Node256Children<K, V>, K, V, A>(partial_key, leaf, children, arena)
   }
 }
 
 impl<'a, K: 'a, V: 'a> NodeRef<Mut<'a>, K, V, Internal> {
-  pub(crate) unsafe fn insert_child(
+  /// Insert a new child with key `k`, and grows current node into a larger node class if it's
+  /// full. When this node is replaced, this node ref is updated to point to new node.
+  ///
+  /// # Safety
+  ///
+  /// This method accepts a raw pointer and owns it afterwards. `root` must point to the root of
+  /// the tree, and `arena` must be the one its nodes are allocated from.
+  pub(crate) unsafe fn insert_child<A: Allocator>(
     &mut self,
-    _k: u8,
-    _node_ptr: BoxedNode<K, V>,
+    k: u8,
+    node_ptr: BoxedNode<K, V>,
+    root: Handle<K, V>,
+    arena: &mut NodeArena<K, V, A>,
   ) -> Option<BoxedNode<K, V>> {
-    todo!()
+    let is_full = with_internal_node!(self, node, as_ref, { node.is_full() });
+    if is_full && self.find_child(k).is_none() {
+      self.grow(root, arena);
+    }
+
+    with_internal_node!(self, node, { InternalNode::set_child(node, k, node_ptr) })
+  }
+
+  /// Replace this node with a node of next larger class.
+  unsafe fn grow<A: Allocator>(&mut self, root: Handle<K, V>, arena: &mut NodeArena<K, V, A>) {
+    let new_ptr: BoxedNode<K, V> = match self.as_base_ref().node_type {
+      NodeType::Node4 => {
+        InternalNode4::change_type::<Node16Children<K, V>, A>(self.inner.cast(), arena).cast()
+      }
+      NodeType::Node16 => {
+        InternalNode16::change_type::<Node48Children<K, V>, A>(self.inner.cast(), arena).cast()
+      }
+      NodeType::Node48 => {
+        InternalNode48::change_type::<Node256Children<K, V>, A>(self.inner.cast(), arena).cast()
+      }
+      NodeType::Node256 | NodeType::Leaf => unreachable!("This should not happen!"),
+    };
+
+    // New node keeps parent pointer of old node.
+    self.inner = new_ptr;
+    self.replace_self_in_parent(Some(new_ptr), root);
+  }
+
+  /// Replace this node with a node of next smaller class, if its children fit into it.
+  pub(crate) unsafe fn shrink<A: Allocator>(
+    &mut self,
+    root: Handle<K, V>,
+    arena: &mut NodeArena<K, V, A>,
+  ) {
+    let children_count = self.children_count();
+    let new_ptr: BoxedNode<K, V> = match self.as_base_ref().node_type {
+      NodeType::Node16 if children_count <= Node4Children::<K, V>::CAPACITY => {
+        InternalNode16::change_type::<Node4Children<K, V>, A>(self.inner.cast(), arena).cast()
+      }
+      NodeType::Node48 if children_count <= Node16Children::<K, V>::CAPACITY => {
+        InternalNode48::change_type::<Node16Children<K, V>, A>(self.inner.cast(), arena).cast()
+      }
+      NodeType::Node256 if children_count <= Node48Children::<K, V>::CAPACITY => {
+        InternalNode256::change_type::<Node48Children<K, V>, A>(self.inner.cast(), arena).cast()
+      }
+      _ => return,
+    };
+
+    // New node keeps parent pointer of old node.
+    self.inner = new_ptr;
+    self.replace_self_in_parent(Some(new_ptr), root);
   }
 
   pub(crate) unsafe fn set_leaf(
     &mut self,
     ptr: NonNull<LeafNode<K, V>>,
   ) -> Option<NonNull<LeafNode<K, V>>> {
-    self.as_internal_mut().set_leaf(ptr)
+    InternalNodeBase::set_leaf(self.inner.cast(), ptr)
   }
 
-  pub(crate) fn set_partial_key(&mut self, new_partial_key: &[u8]) {
-    self.as_internal_mut().set_partial_key(new_partial_key)
+  pub(crate) fn set_partial_key(&mut self, partial_key: PartialKey) {
+    self.as_internal_mut().partial_key = partial_key;
   }
 
-  pub(super) unsafe fn update_child_at(&mut self, child_pos: ChildPos, ptr: Option<BoxedNode<K, V>>) -> Option<BoxedNode<K, V>> {
+  pub(crate) fn prepend_partial_key(&mut self, prefix: &PartialKey, k: u8) {
+    self.as_internal_mut().prepend_partial_key(prefix, k)
+  }
+
+  pub(crate) unsafe fn update_child_at(
+    &mut self,
+    child_pos: ChildPos,
+    ptr: Option<BoxedNode<K, V>>,
+  ) -> Option<BoxedNode<K, V>> {
     with_internal_node!(self, node, {
-      unsafe { node.set_child_at(child_pos, ptr) }
+      InternalNode::set_child_at(node, child_pos, ptr)
     })
   }
 }
-
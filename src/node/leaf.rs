@@ -1,9 +1,12 @@
-use std::mem::swap;
+use std::ptr;
 use std::ptr::NonNull;
 
-use crate::marker::{Immut, Leaf, Mut};
-use crate::node::{ChildPos, InternalNodeBase, NodeRef};
-use crate::node::{NodeBase, NodeType};
+use allocator_api2::alloc::Allocator;
+
+use crate::key::ARTKey;
+use crate::marker::{Dying, Immut, Leaf, Mut, ValMut};
+use crate::node::NodeRef;
+use crate::node::{NodeArena, NodeBase, NodeType};
 
 
 // pub(crate) type BoxedLeafNode<V> = NonNull<LeafNode<V>>;
@@ -16,31 +19,23 @@ pub(crate) struct LeafNode<K, V> {
 }
 
 impl<K, V> LeafNode<K, V> {
-  pub(crate) fn new_root(key: K, value: V) -> Box<Self> {
-    Box::new(Self {
-      node_base: NodeBase::new_root(NodeType::Leaf),
-      key,
-      value,
-    })
-  }
-
-  pub(crate) fn new(parent: NonNull<InternalNodeBase<K, V>>, pos: ChildPos, key: K, value: V) -> Box<Self> {
-    Box::new(Self {
-      node_base: NodeBase::new(NodeType::Leaf, parent, pos),
+  /// Creates a leaf node without parent.
+  pub(crate) fn new(key: K, value: V) -> Self {
+    Self {
+      node_base: NodeBase::new(NodeType::Leaf),
       key,
       value,
-    })
-  }
-
-  pub(crate) fn set_value(&mut self, mut value: V) -> V {
-    swap(&mut self.value, &mut value);
-    value
+    }
   }
 
   pub(crate) fn key_ref(&self) -> &K {
     &self.key
   }
 
+  pub(crate) fn key_mut(&mut self) -> &mut K {
+    &mut self.key
+  }
+
   pub(crate) fn value_mut(&mut self) -> &mut V {
     &mut self.value
   }
@@ -73,37 +68,67 @@ impl<BorrowType, K, V> NodeRef<BorrowType, K, V, Leaf> {
   }
 }
 
-impl<BorrowType, K: AsRef<[u8]>, V> NodeRef<BorrowType, K, V, Leaf> {
-  pub(crate) fn partial_key(&self) -> &[u8] {
-    let leaf = self.as_leaf_ref();
-    let leaf_key_bytes = leaf.key_ref().as_ref();
-    if self.prefix_len >= leaf_key_bytes.len() {
-      &[]
-    } else {
-      &leaf_key_bytes[self.prefix_len..]
-    }
+impl<BorrowType, K: ARTKey, V> NodeRef<BorrowType, K, V, Leaf> {
+  pub(crate) fn key_bytes(&self) -> K::Bytes<'_> {
+    self.as_leaf_ref().key_ref().to_bytes()
   }
 }
 
-impl<'a, K: 'a + AsRef<[u8]>, V: 'a> NodeRef<Mut<'a>, K, V, Leaf> {
+impl<'a, K: 'a, V: 'a> NodeRef<Mut<'a>, K, V, Leaf> {
   pub(crate) fn value_mut(self) -> &'a mut V {
     unsafe { (&mut *self.as_leaf_ptr()).value_mut() }
   }
 
-  pub(crate) fn set_prefix_len(&mut self, new_prefix_len: usize) {
-    debug_assert!(self.as_leaf_ref().key_ref().as_ref().len() >= new_prefix_len);
-    self.prefix_len = new_prefix_len;
+  pub(crate) fn kv_mut(&mut self) -> (&K, &mut V) {
+    let leaf = self.as_leaf_mut();
+    (&leaf.key, &mut leaf.value)
   }
 
-  pub(crate) fn into_kv(self) -> (K, V) {
-    let leaf = unsafe { Box::from_raw(self.inner.cast::<LeafNode<K, V>>().as_ptr()) };
+  pub(crate) fn into_kv_mut(self) -> (&'a K, &'a mut V) {
+    let leaf = unsafe { &mut *self.as_leaf_ptr() };
+    (&leaf.key, &mut leaf.value)
+  }
+
+  /// Moves key and value out of this leaf, and frees it.
+  ///
+  /// # Safety
+  ///
+  /// This leaf must have been detached from tree, and `arena` must be the one it's allocated from.
+  pub(crate) unsafe fn into_kv<A: Allocator>(self, arena: &mut NodeArena<K, V, A>) -> (K, V) {
+    let leaf_ptr = self.inner.cast::<LeafNode<K, V>>();
+    let leaf = leaf_ptr.as_ptr().read();
+    arena.free(leaf_ptr);
     (leaf.key, leaf.value)
   }
 }
 
 impl<'a, K: 'a, V: 'a> NodeRef<Immut<'a>, K, V, Leaf> {
   pub(crate) fn value_ref(&self) -> &'a V {
-    unsafe { (&*self.as_leaf_ptr()).value_ref() }
+    unsafe { (*self.as_leaf_ptr()).value_ref() }
+  }
+
+  pub(crate) fn into_kv_ref(self) -> (&'a K, &'a V) {
+    let leaf = unsafe { &*self.as_leaf_ptr() };
+    (&leaf.key, &leaf.value)
+  }
+}
+
+impl<'a, K: 'a, V: 'a> NodeRef<ValMut<'a>, K, V, Leaf> {
+  pub(crate) fn into_kv_valmut(self) -> (&'a K, &'a mut V) {
+    let leaf = unsafe { &mut *self.as_leaf_ptr() };
+    (&leaf.key, &mut leaf.value)
+  }
+}
+
+impl<K, V> NodeRef<Dying, K, V, Leaf> {
+  /// Moves key and value out of this leaf.
+  ///
+  /// # Safety
+  ///
+  /// This method should be called at most once for each leaf. The leaf is freed with its slab.
+  pub(crate) unsafe fn read_kv(&self) -> (K, V) {
+    let leaf = self.as_leaf_ptr();
+    (ptr::read(&(*leaf).key), ptr::read(&(*leaf).value))
   }
 }
 
@@ -2,11 +2,13 @@ use std::marker::PhantomData;
 use std::mem::MaybeUninit;
 use std::ptr::NonNull;
 
+pub(crate) use arena::NodeArena;
 pub(crate) use internal::*;
 pub(crate) use leaf::*;
 
-use crate::marker::{Immut, Internal, InternalOrLeaf, Leaf, Mut};
+use crate::marker::{Dying, Immut, Internal, InternalOrLeaf, Leaf, Mut};
 
+mod arena;
 mod internal;
 mod node16;
 mod node256;
@@ -29,10 +31,10 @@ pub(crate) enum NodeType {
 
 /// Position of a child in parent node.
 ///
-/// When `idx <= 0xFF`, it's an index in `Children` container.
-/// Otherwise it's a leaf node.
+/// When `idx <= 0xFF`, it's the key byte of the child in parent's `Children` container.
+/// Otherwise it's the leaf slot of parent.
 #[derive(Debug, Copy, Clone)]
-pub(super) struct ChildPos {
+pub(crate) struct ChildPos {
   idx: u16,
 }
 
@@ -55,11 +57,7 @@ pub(crate) struct NodeRef<BorrowType, K, V, NodeType> {
 
 impl<'a, K, V, NodeType> Clone for NodeRef<Immut<'a>, K, V, NodeType> {
   fn clone(&self) -> Self {
-    Self {
-      inner: self.inner,
-      prefix_len: self.prefix_len,
-      _marker: PhantomData,
-    }
+    *self
   }
 }
 
@@ -72,10 +70,7 @@ pub(crate) enum NodeImpl<BorrowType, K, V> {
 
 impl NodeType {
   fn is_internal(&self) -> bool {
-    match self {
-      NodeType::Leaf => false,
-      _ => true,
-    }
+    !matches!(self, NodeType::Leaf)
   }
 
   fn is_leaf(&self) -> bool {
@@ -84,7 +79,9 @@ impl NodeType {
 }
 
 impl<K, V> NodeBase<K, V> {
-  pub(super) fn new_root(node_type: NodeType) -> Self {
+  /// Creates a node without parent. Parent pointer is filled when it's attached to some internal
+  /// node.
+  pub(super) fn new(node_type: NodeType) -> Self {
     Self {
       node_type,
       parent: None,
@@ -92,23 +89,25 @@ impl<K, V> NodeBase<K, V> {
       _marker: PhantomData,
     }
   }
-  pub(super) fn new(node_type: NodeType, parent: NonNull<InternalNodeBase<K, V>>, idx: ChildPos) -> Self {
-    Self {
-      node_type,
-      parent: Some(parent),
-      idx: MaybeUninit::new(idx),
-      _marker: PhantomData,
-    }
+
+  pub(super) fn set_parent(&mut self, parent: NonNull<InternalNodeBase<K, V>>, idx: ChildPos) {
+    self.parent = Some(parent);
+    self.idx = MaybeUninit::new(idx);
   }
 }
 
 impl<BorrowType, K, V, NodeType> NodeRef<BorrowType, K, V, NodeType> {
   /// Returns parent node ref if exists.
   pub(crate) fn ascend(&self) -> Option<NodeRef<BorrowType, K, V, Internal>> {
-    if let Some(parent_ptr) = self.as_base_ref().parent {
-      let parent_prefix_len = self.prefix_len - unsafe {
-        parent_ptr.as_ref().partial_key().len()
-      } - 1;
+    let base = self.as_base_ref();
+    if let Some(parent_ptr) = base.parent {
+      // SAFETY: `idx` is inited when `parent` is not null.
+      let edge_len = match unsafe { base.idx.assume_init() }.to_idx() {
+        Some(_) => 1,
+        None => 0,
+      };
+      let parent_prefix_len =
+        self.prefix_len - unsafe { parent_ptr.as_ref().partial_key_len() } - edge_len;
       Some(NodeRef {
         inner: parent_ptr.cast(),
         prefix_len: parent_prefix_len,
@@ -123,6 +122,13 @@ impl<BorrowType, K, V, NodeType> NodeRef<BorrowType, K, V, NodeType> {
     self.prefix_len
   }
 
+  /// Returns position of this node in parent node, or `None` if it's root.
+  pub(crate) fn child_pos(&self) -> Option<ChildPos> {
+    let base = self.as_base_ref();
+    // SAFETY: `idx` is inited when `parent` is not null.
+    base.parent.map(|_| unsafe { base.idx.assume_init() })
+  }
+
   fn as_base_ref(&self) -> &NodeBase<K, V> {
     unsafe { self.inner.as_ref() }
   }
@@ -139,25 +145,89 @@ impl<BorrowType, K, V, NodeType> NodeRef<BorrowType, K, V, NodeType> {
     self.inner
   }
 
-  pub(crate) fn root_node_ref(ptr: BoxedNode<K, V>, holder: NonNull<Option<BoxedNode<K, V>>>) -> Self {
+  pub(crate) fn root_node_ref(ptr: BoxedNode<K, V>) -> Self {
+    Self::from_raw_parts(ptr, 0)
+  }
+
+  pub(crate) fn from_raw_parts(ptr: BoxedNode<K, V>, prefix_len: usize) -> Self {
     Self {
       inner: ptr,
-      prefix_len: 0,
+      prefix_len,
       _marker: PhantomData,
     }
   }
+
+  /// Creates node ref for a node in tree, whose prefix length is computed by following parent
+  /// pointers.
+  pub(crate) fn from_node_ptr(ptr: BoxedNode<K, V>) -> Self {
+    let mut prefix_len = 0;
+    let mut cur = unsafe { ptr.as_ref() };
+    while let Some(parent) = cur.parent {
+      // SAFETY: `idx` is inited when `parent` is not null.
+      let edge_len = match unsafe { cur.idx.assume_init() }.to_idx() {
+        Some(_) => 1,
+        None => 0,
+      };
+      let parent = unsafe { parent.as_ref() };
+      prefix_len += parent.partial_key_len() + edge_len;
+      cur = parent.node_base();
+    }
+    Self::from_raw_parts(ptr, prefix_len)
+  }
 }
 
 impl<'a, K, V, NodeType> NodeRef<Mut<'a>, K, V, NodeType> {
-  fn as_base_mut(&mut self) -> &mut NodeBase<K, V> {
-    // SAFETY: Borrowed in mut ref.
-    unsafe { self.inner.as_mut() }
+  /// Temporarily takes out another immutable reference to the same node.
+  pub(crate) fn reborrow(&self) -> NodeRef<Immut<'_>, K, V, NodeType> {
+    NodeRef::from_raw_parts(self.inner, self.prefix_len)
   }
 
   /// Write new pointer to holder of this node.
-  pub(crate) unsafe fn replace_self_in_parent(&mut self, new_ptr: Option<BoxedNode<K, V>>) {
+  ///
+  /// The holder is either a slot in parent node, or `root` when this node has no parent.
+  ///
+  /// # Safety
+  ///
+  /// `root` must point to the root of the tree containing this node. The caller is responsible
+  /// for the ownership of this node afterwards.
+  pub(crate) unsafe fn replace_self_in_parent(
+    &mut self,
+    new_ptr: Option<BoxedNode<K, V>>,
+    mut root: Handle<K, V>,
+  ) {
     if let Some(mut parent) = self.ascend() {
       parent.update_child_at(self.inner.as_ref().idx.assume_init(), new_ptr);
+    } else {
+      *root.as_mut() = new_ptr;
+      if let Some(mut new_root) = new_ptr {
+        new_root.as_mut().parent = None;
+      }
+    }
+  }
+}
+
+impl<K, V> NodeRef<Dying, K, V, InternalOrLeaf> {
+  /// Drops keys and values of all leaves in the subtree rooted at this node. Nodes themselves are
+  /// freed with slabs of their arena.
+  ///
+  /// Internal nodes without entries are allowed, e.g. in a tree which is being built.
+  ///
+  /// # Safety
+  ///
+  /// Keys and values of this subtree must not have been moved out, and no other reference to the
+  /// subtree exists.
+  pub(crate) unsafe fn drop_key_values(self) {
+    let mut stack = vec![self.inner];
+    while let Some(ptr) = stack.pop() {
+      match NodeRef::<Dying, K, V, InternalOrLeaf>::root_node_ref(ptr).downcast() {
+        NodeImpl::Leaf(leaf) => drop(leaf.read_kv()),
+        NodeImpl::Internal(internal) => {
+          if let Some(leaf) = internal.get_leaf() {
+            stack.push(leaf.get_inner());
+          }
+          internal.for_each_child(|_, child| stack.push(child.get_inner()));
+        }
+      }
     }
   }
 }
@@ -181,11 +251,11 @@ impl<BorrowType, K, V> NodeRef<BorrowType, K, V, InternalOrLeaf> {
 
 impl ChildPos {
   /// Returns child index of this position. The result is `None` if it's leaf.
-  fn to_idx(self) -> Option<u32> {
+  pub(crate) fn to_idx(self) -> Option<u8> {
     if self.idx > 0xFF {
       None
     } else {
-      Some(self.idx as u32)
+      Some(self.idx as u8)
     }
   }
 }
@@ -193,10 +263,10 @@ impl ChildPos {
 impl From<Option<u8>> for ChildPos {
   fn from(input: Option<u8>) -> Self {
     match input {
-      // An index in `Children` container.
+      // Key byte in `Children` container.
       Some(idx) => Self { idx: idx as u16 },
       // Leaf
-      None => Self { idx: 0xFFFF }
+      None => Self { idx: 0xFFFF },
     }
   }
 }
@@ -2,29 +2,157 @@ use crate::node::{BoxedNode, Children, NodeType};
 
 const NODE16_CAPACITY: usize = 16;
 
+/// Children are stored with keys in ascending order, and occupied slots are always in front.
 pub(crate) struct Node16Children<K, V> {
-  _keys: [u8; NODE16_CAPACITY],
-  _children: [BoxedNode<K, V>; NODE16_CAPACITY],
+  keys: [u8; NODE16_CAPACITY],
+  children: [Option<BoxedNode<K, V>>; NODE16_CAPACITY],
+  len: u8,
+}
+
+impl<K, V> Node16Children<K, V> {
+  #[inline(always)]
+  fn len(&self) -> usize {
+    self.len as usize
+  }
+
+  /// Returns position of key `k` in occupied slots.
+  #[inline(always)]
+  fn position(&self, k: u8) -> Option<usize> {
+    find_key(&self.keys, self.len(), k)
+  }
+}
+
+/// Compares `k` against all keys at once, and returns position of the first match within `len`.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn find_key(keys: &[u8; NODE16_CAPACITY], len: usize, k: u8) -> Option<usize> {
+  use std::arch::x86_64::{
+    __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_set1_epi8,
+  };
+
+  // SAFETY: SSE2 is always available on x86_64, and `keys` has 16 bytes.
+  let mask = unsafe {
+    let keys = _mm_loadu_si128(keys.as_ptr() as *const __m128i);
+    _mm_movemask_epi8(_mm_cmpeq_epi8(keys, _mm_set1_epi8(k as i8))) as u32
+  };
+  let mask = mask & ((1 << len) - 1);
+  if mask == 0 {
+    None
+  } else {
+    Some(mask.trailing_zeros() as usize)
+  }
+}
+
+/// Compares `k` against all keys at once, and returns position of the first match within `len`.
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn find_key(keys: &[u8; NODE16_CAPACITY], len: usize, k: u8) -> Option<usize> {
+  use std::arch::aarch64::{
+    vceqq_u8, vdupq_n_u8, vget_lane_u64, vld1q_u8, vreinterpret_u64_u8, vreinterpretq_u16_u8,
+    vshrn_n_u16,
+  };
+
+  // NEON has no movemask, so each byte of comparison result is narrowed into 4 bits instead.
+  // SAFETY: NEON is always available on aarch64, and `keys` has 16 bytes.
+  let mask = unsafe {
+    let eq = vceqq_u8(vld1q_u8(keys.as_ptr()), vdupq_n_u8(k));
+    let nibbles = vshrn_n_u16::<4>(vreinterpretq_u16_u8(eq));
+    vget_lane_u64::<0>(vreinterpret_u64_u8(nibbles))
+  };
+  let mask = if len == NODE16_CAPACITY {
+    mask
+  } else {
+    mask & ((1 << (4 * len)) - 1)
+  };
+  if mask == 0 {
+    None
+  } else {
+    Some(mask.trailing_zeros() as usize / 4)
+  }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[inline(always)]
+fn find_key(keys: &[u8; NODE16_CAPACITY], len: usize, k: u8) -> Option<usize> {
+  keys[0..len].iter().position(|key| *key == k)
 }
 
 impl<K, V> Default for Node16Children<K, V> {
   fn default() -> Self {
-    todo!()
+    Self {
+      keys: [0; NODE16_CAPACITY],
+      children: [None; NODE16_CAPACITY],
+      len: 0,
+    }
   }
 }
 
 impl<K, V> Children<K, V> for Node16Children<K, V> {
   const NODE_TYPE: NodeType = NodeType::Node16;
+  const CAPACITY: usize = NODE16_CAPACITY;
+
+  unsafe fn set_child(&mut self, k: u8, node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>> {
+    let len = self.len();
+    let pos = self.keys[0..len].iter().position(|key| *key >= k).unwrap_or(len);
+    if pos < len && self.keys[pos] == k {
+      return self.children[pos].replace(node);
+    }
+
+    assert!(len < NODE16_CAPACITY, "Node16 is full!");
+    self.keys.copy_within(pos..len, pos + 1);
+    self.children.copy_within(pos..len, pos + 1);
+    self.keys[pos] = k;
+    self.children[pos] = Some(node);
+    self.len += 1;
+    None
+  }
 
-  unsafe fn set_child(&mut self, _k: u8, _node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>> {
-    todo!()
+  fn remove_child(&mut self, k: u8) -> Option<BoxedNode<K, V>> {
+    let len = self.len();
+    let pos = self.position(k)?;
+    let ret = self.children[pos].take();
+    self.keys.copy_within((pos + 1)..len, pos);
+    self.children.copy_within((pos + 1)..len, pos);
+    self.children[len - 1] = None;
+    self.len -= 1;
+    ret
   }
 
-  unsafe fn set_child_at(&mut self, _idx: usize, _node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>> {
-    todo!()
+  fn find_child(&self, k: u8) -> Option<BoxedNode<K, V>> {
+    self.children[self.position(k)?]
   }
 
-  fn child_at(&self, _idx: usize) -> Option<BoxedNode<K, V>> {
-    todo!()
+  fn next_child(&self, k: u8) -> Option<(u8, BoxedNode<K, V>)> {
+    let len = self.len();
+    let pos = self.keys[0..len].iter().position(|key| *key >= k)?;
+    self.children[pos].map(|c| (self.keys[pos], c))
+  }
+
+  fn prev_child(&self, k: u8) -> Option<(u8, BoxedNode<K, V>)> {
+    let len = self.len();
+    let pos = self.keys[0..len].iter().rposition(|key| *key <= k)?;
+    self.children[pos].map(|c| (self.keys[pos], c))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn find_key_matches_scalar() {
+    for len in 0..=NODE16_CAPACITY {
+      for stale in [0, 0x80, 0xFF] {
+        // Ascending keys spread over all bytes, and stale bytes in unoccupied slots.
+        let mut keys = [stale; NODE16_CAPACITY];
+        for (i, key) in keys[..len].iter_mut().enumerate() {
+          *key = (i * 17) as u8;
+        }
+        for k in 0..=u8::MAX {
+          let expected = keys[..len].iter().position(|key| *key == k);
+          assert_eq!(find_key(&keys, len, k), expected, "len {} key {}", len, k);
+        }
+      }
+    }
   }
 }
@@ -3,26 +3,40 @@ use crate::node::{BoxedNode, Children, NodeType};
 pub(in crate::node) const NODE256_CAPACITY: usize = 256;
 
 pub(crate) struct Node256Children<K, V> {
-  _children: [BoxedNode<K, V>; NODE256_CAPACITY],
+  children: [Option<BoxedNode<K, V>>; NODE256_CAPACITY],
 }
 
 impl<K, V> Default for Node256Children<K, V> {
   fn default() -> Self {
-    todo!()
+    Self {
+      children: [None; NODE256_CAPACITY],
+    }
   }
 }
 
 impl<K, V> Children<K, V> for Node256Children<K, V> {
   const NODE_TYPE: NodeType = NodeType::Node256;
-  unsafe fn set_child(&mut self, _k: u8, _node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>> {
-    todo!()
+  const CAPACITY: usize = NODE256_CAPACITY;
+
+  unsafe fn set_child(&mut self, k: u8, node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>> {
+    self.children[k as usize].replace(node)
+  }
+
+  fn remove_child(&mut self, k: u8) -> Option<BoxedNode<K, V>> {
+    self.children[k as usize].take()
+  }
+
+  fn find_child(&self, k: u8) -> Option<BoxedNode<K, V>> {
+    self.children[k as usize]
   }
 
-  unsafe fn set_child_at(&mut self, _idx: usize, _node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>> {
-    todo!()
+  fn next_child(&self, k: u8) -> Option<(u8, BoxedNode<K, V>)> {
+    (k..=u8::MAX).find_map(|key| self.children[key as usize].map(|c| (key, c)))
   }
 
-  fn child_at(&self, _idx: usize) -> Option<BoxedNode<K, V>> {
-    todo!()
+  fn prev_child(&self, k: u8) -> Option<(u8, BoxedNode<K, V>)> {
+    (0..=k)
+      .rev()
+      .find_map(|key| self.children[key as usize].map(|c| (key, c)))
   }
 }
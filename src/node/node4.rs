@@ -2,29 +2,75 @@ use crate::node::{BoxedNode, Children, NodeType};
 
 const NODE4_CAPACITY: usize = 4;
 
+/// Children are stored with keys in ascending order, and occupied slots are always in front.
 pub(crate) struct Node4Children<K, V> {
-  _keys: [u8; NODE4_CAPACITY],
-  _children: [BoxedNode<K, V>; NODE4_CAPACITY],
+  keys: [u8; NODE4_CAPACITY],
+  children: [Option<BoxedNode<K, V>>; NODE4_CAPACITY],
+}
+
+impl<K, V> Node4Children<K, V> {
+  fn len(&self) -> usize {
+    self
+      .children
+      .iter()
+      .position(|c| c.is_none())
+      .unwrap_or(NODE4_CAPACITY)
+  }
 }
 
 impl<K, V> Default for Node4Children<K, V> {
   fn default() -> Self {
-    todo!()
+    Self {
+      keys: [0; NODE4_CAPACITY],
+      children: [None; NODE4_CAPACITY],
+    }
   }
 }
 
 impl<K, V> Children<K, V> for Node4Children<K, V> {
   const NODE_TYPE: NodeType = NodeType::Node4;
+  const CAPACITY: usize = NODE4_CAPACITY;
+
+  unsafe fn set_child(&mut self, k: u8, node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>> {
+    let len = self.len();
+    let pos = self.keys[0..len].iter().position(|key| *key >= k).unwrap_or(len);
+    if pos < len && self.keys[pos] == k {
+      return self.children[pos].replace(node);
+    }
+
+    assert!(len < NODE4_CAPACITY, "Node4 is full!");
+    self.keys.copy_within(pos..len, pos + 1);
+    self.children.copy_within(pos..len, pos + 1);
+    self.keys[pos] = k;
+    self.children[pos] = Some(node);
+    None
+  }
+
+  fn remove_child(&mut self, k: u8) -> Option<BoxedNode<K, V>> {
+    let len = self.len();
+    let pos = self.keys[0..len].iter().position(|key| *key == k)?;
+    let ret = self.children[pos].take();
+    self.keys.copy_within((pos + 1)..len, pos);
+    self.children.copy_within((pos + 1)..len, pos);
+    self.children[len - 1] = None;
+    ret
+  }
 
-  unsafe fn set_child(&mut self, _k: u8, _node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>> {
-    todo!()
+  fn find_child(&self, k: u8) -> Option<BoxedNode<K, V>> {
+    let len = self.len();
+    let pos = self.keys[0..len].iter().position(|key| *key == k)?;
+    self.children[pos]
   }
 
-  unsafe fn set_child_at(&mut self, _idx: usize, _node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>> {
-    todo!()
+  fn next_child(&self, k: u8) -> Option<(u8, BoxedNode<K, V>)> {
+    let len = self.len();
+    let pos = self.keys[0..len].iter().position(|key| *key >= k)?;
+    self.children[pos].map(|c| (self.keys[pos], c))
   }
 
-  fn child_at(&self, _idx: usize) -> Option<BoxedNode<K, V>> {
-    todo!()
+  fn prev_child(&self, k: u8) -> Option<(u8, BoxedNode<K, V>)> {
+    let len = self.len();
+    let pos = self.keys[0..len].iter().rposition(|key| *key <= k)?;
+    self.children[pos].map(|c| (self.keys[pos], c))
   }
 }
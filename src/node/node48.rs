@@ -1,31 +1,71 @@
 use crate::node::node256::NODE256_CAPACITY;
 use crate::node::{BoxedNode, Children, NodeType};
 
-const NODE48_CAPACITY: usize = 256;
+const NODE48_CAPACITY: usize = 48;
 
+/// `keys` maps a key byte to its slot in `children`. `0` means empty, otherwise it's slot index
+/// plus one.
 pub(crate) struct Node48Children<K, V> {
-  _keys: [u8; NODE256_CAPACITY],
-  _children: [BoxedNode<K, V>; NODE48_CAPACITY],
+  keys: [u8; NODE256_CAPACITY],
+  children: [Option<BoxedNode<K, V>>; NODE48_CAPACITY],
+}
+
+impl<K, V> Node48Children<K, V> {
+  #[inline(always)]
+  fn slot(&self, k: u8) -> Option<usize> {
+    match self.keys[k as usize] {
+      0 => None,
+      idx => Some(idx as usize - 1),
+    }
+  }
 }
 
 impl<K, V> Default for Node48Children<K, V> {
   fn default() -> Self {
-    todo!()
+    Self {
+      keys: [0; NODE256_CAPACITY],
+      children: [None; NODE48_CAPACITY],
+    }
   }
 }
 
 impl<K, V> Children<K, V> for Node48Children<K, V> {
   const NODE_TYPE: NodeType = NodeType::Node48;
+  const CAPACITY: usize = NODE48_CAPACITY;
+
+  unsafe fn set_child(&mut self, k: u8, node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>> {
+    if let Some(slot) = self.slot(k) {
+      return self.children[slot].replace(node);
+    }
+
+    let slot = self
+      .children
+      .iter()
+      .position(|c| c.is_none())
+      .expect("Node48 is full!");
+    self.children[slot] = Some(node);
+    self.keys[k as usize] = (slot + 1) as u8;
+    None
+  }
+
+  fn remove_child(&mut self, k: u8) -> Option<BoxedNode<K, V>> {
+    let slot = self.slot(k)?;
+    self.keys[k as usize] = 0;
+    self.children[slot].take()
+  }
 
-  unsafe fn set_child(&mut self, _k: u8, _node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>> {
-    todo!()
+  fn find_child(&self, k: u8) -> Option<BoxedNode<K, V>> {
+    self.children[self.slot(k)?]
   }
 
-  unsafe fn set_child_at(&mut self, _idx: usize, _node: BoxedNode<K, V>) -> Option<BoxedNode<K, V>> {
-    todo!()
+  fn next_child(&self, k: u8) -> Option<(u8, BoxedNode<K, V>)> {
+    (k..=u8::MAX)
+      .find_map(|key| self.slot(key).and_then(|slot| self.children[slot]).map(|c| (key, c)))
   }
 
-  fn child_at(&self, _idx: usize) -> Option<BoxedNode<K, V>> {
-    todo!()
+  fn prev_child(&self, k: u8) -> Option<(u8, BoxedNode<K, V>)> {
+    (0..=k)
+      .rev()
+      .find_map(|key| self.slot(key).and_then(|slot| self.children[slot]).map(|c| (key, c)))
   }
 }
@@ -0,0 +1,59 @@
+use allocator_api2::alloc::Allocator;
+
+use crate::marker::{Internal, Leaf, Mut};
+use crate::node::{Handle, NodeArena, NodeImpl, NodeRef};
+
+impl<'a, K: 'a, V: 'a> NodeRef<Mut<'a>, K, V, Leaf> {
+  /// Remove this leaf from tree and returns its key and value.
+  ///
+  /// The parent of this leaf is shrunk or merged afterwards, so that the tree has the same shape
+  /// as if this key was never inserted.
+  ///
+  /// # Safety
+  ///
+  /// `root` must point to the root of the tree containing this node, and `arena` must be the one
+  /// its nodes are allocated from.
+  pub(crate) unsafe fn remove_leaf<A: Allocator>(
+    mut self,
+    root: Handle<K, V>,
+    arena: &mut NodeArena<K, V, A>,
+  ) -> (K, V) {
+    let parent = self.ascend();
+    self.replace_self_in_parent(None, root);
+    if let Some(parent) = parent {
+      parent.handle_underfull(root, arena);
+    }
+    self.into_kv(arena)
+  }
+}
+
+impl<'a, K: 'a, V: 'a> NodeRef<Mut<'a>, K, V, Internal> {
+  /// Fix this node after one of its children or leaf has been removed.
+  ///
+  /// A node left with only its leaf is replaced by the leaf. A node left with only one child is
+  /// merged into that child, with partial keys concatenated. Otherwise it's shrunk into a smaller
+  /// node class when possible.
+  unsafe fn handle_underfull<A: Allocator>(
+    mut self,
+    root: Handle<K, V>,
+    arena: &mut NodeArena<K, V, A>,
+  ) {
+    match (self.children_count(), self.get_leaf()) {
+      (0, Some(leaf)) => {
+        self.replace_self_in_parent(Some(leaf.get_inner()), root);
+        self.deallocate(arena);
+      }
+      (1, None) => {
+        let (k, child) = self.next_child(0).expect("Child count mismatch!");
+        let child_ptr = child.get_inner();
+        if let NodeImpl::Internal(mut child) = child.downcast() {
+          child.prepend_partial_key(self.partial_key(), k);
+        }
+        self.replace_self_in_parent(Some(child_ptr), root);
+        self.deallocate(arena);
+      }
+      (0, None) => unreachable!("Internal node should have at least two entries!"),
+      _ => self.shrink(root, arena),
+    }
+  }
+}
@@ -1,5 +1,5 @@
-use std::cmp::Ordering;
-
+use crate::common_len;
+use crate::key::ARTKey;
 use crate::marker;
 use crate::marker::{Internal, InternalOrLeaf, Leaf};
 use crate::node::{NodeImpl, NodeRef};
@@ -11,72 +11,128 @@ pub(crate) enum SearchResult<BorrowType, K, V> {
   NotFound(NodeRef<BorrowType, K, V, InternalOrLeaf>),
 }
 
-impl<BorrowType: marker::BorrowType, K: AsRef<[u8]>, V> NodeRef<BorrowType, K, V, InternalOrLeaf> {
-  pub(crate) fn search_tree(self, key: &K) -> SearchResult<BorrowType, K, V> {
+impl<BorrowType: marker::BorrowType, K: ARTKey, V> NodeRef<BorrowType, K, V, InternalOrLeaf> {
+  /// Search `key` in this subtree.
+  ///
+  /// Unstored bytes of truncated partial keys are skipped on the way down, so the key is verified
+  /// against the leaf found in that case. When the key is not found, the returned node is where
+  /// it diverges from keys in tree.
+  pub(crate) fn search_tree(self, key: &[u8]) -> SearchResult<BorrowType, K, V> {
     let mut cur = self;
+    let mut skipped = false;
 
-    loop {
+    let result = loop {
       match cur.downcast() {
-        NodeImpl::Internal(internal) => match internal.search_node(key.as_ref()) {
-          SearchResult::Found(node) => return SearchResult::Found(node),
-          SearchResult::GoDown(node) => {
-            cur = node;
+        NodeImpl::Internal(internal) => {
+          skipped |= internal.is_partial_key_truncated();
+          match internal.search_node(key) {
+            SearchResult::Found(leaf) if skipped => break leaf.search_node(key),
+            SearchResult::GoDown(node) => {
+              cur = node;
+            }
+            result => break result,
           }
-          SearchResult::NotFound(ret) => return SearchResult::NotFound(ret)
-        },
+        }
         NodeImpl::Leaf(leaf) => {
-          return leaf.search_node(key);
+          break leaf.search_node(key);
         }
       }
+    };
+
+    match result {
+      NotFound(node) if skipped => NotFound(node.find_divergence(key)),
+      result => result,
     }
   }
-}
 
-impl<BorrowType: marker::BorrowType, K, V> NodeRef<BorrowType, K, V, Internal> {
-  fn search_node(self, key: &[u8]) -> SearchResult<BorrowType, K, V> {
-    if self.prefix_len() >= key.len() {
-      return NotFound(self.forget_type());
+  /// Returns the deepest node on the path to this node, whose prefix is shared by `key`.
+  fn find_divergence(self, key: &[u8]) -> Self {
+    let common_key_len = {
+      let leaf = NodeRef::<BorrowType, K, V, InternalOrLeaf>::from_raw_parts(
+        self.get_inner(),
+        self.prefix_len(),
+      )
+      .minimum_leaf();
+      let leaf_key = leaf.key_bytes();
+      common_len(leaf_key.as_ref(), key)
+    };
+
+    let mut cur = self;
+    while cur.prefix_len() > common_key_len {
+      cur = cur.ascend().expect("Root has empty prefix!").forget_type();
     }
+    cur
+  }
+}
 
-    let input_partial_prefix = &key[self.prefix_len()..];
-    let this_partial_prefix = self.partial_key();
+impl<BorrowType: marker::BorrowType, K: ARTKey, V> NodeRef<BorrowType, K, V, InternalOrLeaf> {
+  /// Search the root of subtree containing all keys starting with `prefix`.
+  pub(crate) fn search_prefix(self, prefix: &[u8]) -> Option<NodeRef<BorrowType, K, V, InternalOrLeaf>> {
+    let mut cur = self;
 
-    if input_partial_prefix.len() > this_partial_prefix.len() {
-      match &input_partial_prefix[0..this_partial_prefix.len()].cmp(this_partial_prefix) {
-        Ordering::Equal => {
-          let new_prefix_len = self.prefix_len() + self.partial_key().len();
+    loop {
+      match cur.downcast() {
+        NodeImpl::Internal(internal) => {
+          if internal.prefix_len() >= prefix.len() {
+            return Some(internal.forget_type());
+          }
 
-          let k = key[new_prefix_len];
+          let input_partial_prefix = &prefix[internal.prefix_len()..];
+          let len = internal.with_partial_key(|this_partial_prefix| {
+            let len = input_partial_prefix.len().min(this_partial_prefix.len());
+            (input_partial_prefix[0..len] == this_partial_prefix[0..len]).then_some(len)
+          })?;
 
-          match self.find_child(k) {
-            Some(handle) => GoDown(handle),
-            None => NotFound(self.forget_type()),
+          if input_partial_prefix.len() <= internal.partial_key_len() {
+            return Some(internal.forget_type());
           }
+          cur = internal.find_child(input_partial_prefix[len])?;
+        }
+        NodeImpl::Leaf(leaf) => {
+          return if leaf.key_bytes().as_ref().starts_with(prefix) {
+            Some(leaf.forget_type())
+          } else {
+            None
+          };
         }
-        _ => NotFound(self.forget_type()),
-      }
-    } else if input_partial_prefix.len() == this_partial_prefix.len() {
-      match input_partial_prefix.cmp(this_partial_prefix) {
-        Ordering::Equal => match self.get_leaf() {
-          Some(leaf) => Found(leaf),
-          None => NotFound(self.forget_type()),
-        },
-        _ => NotFound(self.forget_type()),
       }
-    } else {
-      NotFound(self.forget_type())
     }
   }
 }
 
-impl<BorrowType, K: AsRef<[u8]>, V> NodeRef<BorrowType, K, V, Leaf> {
-  fn search_node(self, key: &K) -> SearchResult<BorrowType, K, V> {
-    let key = key.as_ref();
-    if self.prefix_len() >= key.len() && self.partial_key().len() == 0 {
-      return SearchResult::Found(self);
+impl<BorrowType: marker::BorrowType, K, V> NodeRef<BorrowType, K, V, Internal> {
+  fn search_node(self, key: &[u8]) -> SearchResult<BorrowType, K, V> {
+    // Key may end exactly at this node, in which case it's stored in leaf slot.
+    let input_partial_prefix = match key.get(self.prefix_len()..) {
+      Some(input_partial_prefix) => input_partial_prefix,
+      None => return NotFound(self.forget_type()),
+    };
+    let partial_key_len = self.partial_key_len();
+    // Only stored bytes are compared, and the rest are verified at leaf.
+    let this_partial_prefix = self.stored_partial_key();
+    if input_partial_prefix.len() < partial_key_len
+      || input_partial_prefix[0..this_partial_prefix.len()] != *this_partial_prefix
+    {
+      return NotFound(self.forget_type());
+    }
+
+    match key.get(self.prefix_len() + partial_key_len) {
+      Some(&k) => match self.find_child(k) {
+        Some(handle) => GoDown(handle),
+        None => NotFound(self.forget_type()),
+      },
+      None => match self.get_leaf() {
+        Some(leaf) => Found(leaf),
+        None => NotFound(self.forget_type()),
+      },
     }
-    let input_partial_prefix = &key[self.prefix_len()..];
-    if input_partial_prefix == self.partial_key() {
+  }
+}
+
+impl<BorrowType, K: ARTKey, V> NodeRef<BorrowType, K, V, Leaf> {
+  fn search_node(self, key: &[u8]) -> SearchResult<BorrowType, K, V> {
+    // Leaf stores the full key, so compare it entirely.
+    if self.key_bytes().as_ref() == key {
       SearchResult::Found(self)
     } else {
       SearchResult::NotFound(self.forget_type())
@@ -0,0 +1,95 @@
+//! `Serialize` and `Deserialize` implementations, enabled by the `serde` feature.
+//!
+//! Maps are serialized as plain maps and sets as sequences, both in key order. Deserializing
+//! sorted input appends each key after the previous one, without searching from root.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use allocator_api2::alloc::Allocator;
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::key::ARTKey;
+use crate::map::ARTMap;
+use crate::set::ARTSet;
+
+impl<K: Serialize, V: Serialize, A: Allocator> Serialize for ARTMap<K, V, A> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(self)
+  }
+}
+
+impl<'de, K, V> Deserialize<'de> for ARTMap<K, V>
+where
+  K: Deserialize<'de> + ARTKey,
+  V: Deserialize<'de>,
+{
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    struct MapVisitor<K, V> {
+      marker: PhantomData<fn() -> ARTMap<K, V>>,
+    }
+
+    impl<'de, K, V> Visitor<'de> for MapVisitor<K, V>
+    where
+      K: Deserialize<'de> + ARTKey,
+      V: Deserialize<'de>,
+    {
+      type Value = ARTMap<K, V>;
+
+      fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a map")
+      }
+
+      fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut map = ARTMap::new();
+        let mut last = None;
+        while let Some((key, value)) = access.next_entry()? {
+          // SAFETY: `last` is the leaf with maximum key returned by previous insertion.
+          last = Some(unsafe { map.insert_after(last, key, value) });
+        }
+        Ok(map)
+      }
+    }
+
+    deserializer.deserialize_map(MapVisitor {
+      marker: PhantomData,
+    })
+  }
+}
+
+impl<K: Serialize> Serialize for ARTSet<K> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(self)
+  }
+}
+
+impl<'de, K: Deserialize<'de> + ARTKey> Deserialize<'de> for ARTSet<K> {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    struct SetVisitor<K> {
+      marker: PhantomData<fn() -> ARTSet<K>>,
+    }
+
+    impl<'de, K: Deserialize<'de> + ARTKey> Visitor<'de> for SetVisitor<K> {
+      type Value = ARTSet<K>;
+
+      fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a sequence")
+      }
+
+      fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut set = ARTSet::new();
+        let mut last = None;
+        while let Some(key) = access.next_element()? {
+          // SAFETY: `last` is the leaf with maximum key returned by previous insertion.
+          last = Some(unsafe { set.map.insert_after(last, key, ()) });
+        }
+        Ok(set)
+      }
+    }
+
+    deserializer.deserialize_seq(SetVisitor {
+      marker: PhantomData,
+    })
+  }
+}
@@ -0,0 +1,561 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Debug;
+use std::iter::{FromIterator, FusedIterator};
+use std::mem;
+use std::ops::RangeBounds;
+
+use crate::key::{ARTBorrow, ARTKey};
+use crate::map;
+use crate::map::ARTMap;
+
+/// An ordered set based on `ARTMap`.
+///
+/// Elements are ordered by their encoded key bytes, see [`ARTKey`].
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ARTSet<K> {
+  pub(crate) map: ARTMap<K, ()>,
+}
+
+impl<K> Default for ARTSet<K> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<K> ARTSet<K> {
+  pub fn new() -> Self {
+    Self { map: ARTMap::new() }
+  }
+
+  /// Returns the number of elements in the set.
+  pub fn len(&self) -> usize {
+    self.map.len()
+  }
+
+  /// Returns `true` if the set contains no elements.
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+
+  /// Clears the set, removing all elements.
+  pub fn clear(&mut self) {
+    self.map.clear()
+  }
+
+  /// Gets an iterator that visits the elements in the set in ascending order.
+  pub fn iter(&self) -> Iter<'_, K> {
+    Iter {
+      iter: self.map.keys(),
+    }
+  }
+
+  /// Returns the first element in the set, if any.
+  pub fn first(&self) -> Option<&K> {
+    self.map.first_key_value().map(|(k, _)| k)
+  }
+
+  /// Returns the last element in the set, if any.
+  pub fn last(&self) -> Option<&K> {
+    self.map.last_key_value().map(|(k, _)| k)
+  }
+}
+
+impl<K: ARTKey> ARTSet<K> {
+  /// Adds a value to the set.
+  ///
+  /// Returns whether the value was newly inserted.
+  pub fn insert(&mut self, value: K) -> bool {
+    match self.map.entry(value) {
+      map::Entry::Occupied(_) => false,
+      map::Entry::Vacant(entry) => {
+        entry.insert(());
+        true
+      }
+    }
+  }
+
+  /// Returns `true` if the set contains an element equal to the value.
+  pub fn contains<Q>(&self, value: &Q) -> bool
+  where
+    K: ARTBorrow<Q>,
+    Q: ARTKey + ?Sized,
+  {
+    self.map.get(value).is_some()
+  }
+
+  /// Removes a value from the set. Returns whether the value was present in the set.
+  pub fn remove<Q>(&mut self, value: &Q) -> bool
+  where
+    K: ARTBorrow<Q>,
+    Q: ARTKey + ?Sized,
+  {
+    self.map.remove(value).is_some()
+  }
+
+  /// Constructs a double-ended iterator over a sub-range of elements in the set.
+  ///
+  /// # Panics
+  ///
+  /// Panics if range `start > end`, or if range `start == end` and both bounds are `Excluded`.
+  pub fn range<Q, R>(&self, range: R) -> Range<'_, K>
+  where
+    K: ARTBorrow<Q>,
+    Q: ARTKey + ?Sized,
+    R: RangeBounds<Q>,
+  {
+    Range {
+      iter: self.map.range(range),
+    }
+  }
+
+  /// Constructs a double-ended iterator over elements whose key bytes start with `prefix`.
+  pub fn prefix_iter(&self, prefix: &[u8]) -> Range<'_, K> {
+    Range {
+      iter: self.map.prefix_iter(prefix),
+    }
+  }
+
+  /// Visits the elements representing the union, i.e., all the elements in `self` or `other`,
+  /// without duplicates, in ascending order.
+  pub fn union<'a>(&'a self, other: &'a ARTSet<K>) -> Union<'a, K> {
+    Union(MergeIter::new(self, other))
+  }
+
+  /// Visits the elements representing the intersection, i.e., the elements that are both in
+  /// `self` and `other`, in ascending order.
+  pub fn intersection<'a>(&'a self, other: &'a ARTSet<K>) -> Intersection<'a, K> {
+    Intersection(MergeIter::new(self, other))
+  }
+
+  /// Visits the elements representing the difference, i.e., the elements that are in `self` but
+  /// not in `other`, in ascending order.
+  pub fn difference<'a>(&'a self, other: &'a ARTSet<K>) -> Difference<'a, K> {
+    Difference(MergeIter::new(self, other))
+  }
+
+  /// Visits the elements representing the symmetric difference, i.e., the elements that are in
+  /// `self` or in `other` but not in both, in ascending order.
+  pub fn symmetric_difference<'a>(&'a self, other: &'a ARTSet<K>) -> SymmetricDifference<'a, K> {
+    SymmetricDifference(MergeIter::new(self, other))
+  }
+
+  /// Returns `true` if the set is a subset of another, i.e., `other` contains at least all the
+  /// elements in `self`.
+  pub fn is_subset(&self, other: &ARTSet<K>) -> bool {
+    self.len() <= other.len() && self.difference(other).next().is_none()
+  }
+
+  /// Returns `true` if the set is a superset of another, i.e., `self` contains at least all the
+  /// elements in `other`.
+  pub fn is_superset(&self, other: &ARTSet<K>) -> bool {
+    other.is_subset(self)
+  }
+
+  /// Returns `true` if `self` has no elements in common with `other`.
+  pub fn is_disjoint(&self, other: &ARTSet<K>) -> bool {
+    self.intersection(other).next().is_none()
+  }
+}
+
+impl<K: Debug> Debug for ARTSet<K> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_set().entries(self.iter()).finish()
+  }
+}
+
+impl<K: ARTKey> FromIterator<K> for ARTSet<K> {
+  fn from_iter<T: IntoIterator<Item = K>>(iter: T) -> Self {
+    let mut set = ARTSet::new();
+    set.extend(iter);
+    set
+  }
+}
+
+impl<K: ARTKey> Extend<K> for ARTSet<K> {
+  fn extend<T: IntoIterator<Item = K>>(&mut self, iter: T) {
+    iter.into_iter().for_each(move |elem| {
+      self.insert(elem);
+    });
+  }
+}
+
+impl<'a, K: ARTKey + Copy + 'a> Extend<&'a K> for ARTSet<K> {
+  fn extend<I: IntoIterator<Item = &'a K>>(&mut self, iter: I) {
+    self.extend(iter.into_iter().cloned());
+  }
+}
+
+impl<'a, K> IntoIterator for &'a ARTSet<K> {
+  type Item = &'a K;
+  type IntoIter = Iter<'a, K>;
+
+  fn into_iter(self) -> Iter<'a, K> {
+    self.iter()
+  }
+}
+
+impl<K> IntoIterator for ARTSet<K> {
+  type Item = K;
+  type IntoIter = IntoIter<K>;
+
+  fn into_iter(self) -> IntoIter<K> {
+    IntoIter {
+      iter: self.map.into_iter(),
+    }
+  }
+}
+
+/// An iterator over the elements of an `ARTSet`.
+pub struct Iter<'a, K> {
+  iter: map::Keys<'a, K, ()>,
+}
+
+/// An owning iterator over the elements of an `ARTSet`.
+pub struct IntoIter<K> {
+  iter: map::IntoIter<K, ()>,
+}
+
+/// An iterator over a sub-range of elements in an `ARTSet`.
+pub struct Range<'a, K> {
+  iter: map::Range<'a, K, ()>,
+}
+
+impl<'a, K> Clone for Iter<'a, K> {
+  fn clone(&self) -> Self {
+    Iter {
+      iter: self.iter.clone(),
+    }
+  }
+}
+
+impl<'a, K: 'a> Iterator for Iter<'a, K> {
+  type Item = &'a K;
+
+  fn next(&mut self) -> Option<&'a K> {
+    self.iter.next()
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.iter.size_hint()
+  }
+}
+
+impl<'a, K: 'a> DoubleEndedIterator for Iter<'a, K> {
+  fn next_back(&mut self) -> Option<&'a K> {
+    self.iter.next_back()
+  }
+}
+
+impl<'a, K: 'a> ExactSizeIterator for Iter<'a, K> {}
+
+impl<'a, K: 'a> FusedIterator for Iter<'a, K> {}
+
+impl<K> Iterator for IntoIter<K> {
+  type Item = K;
+
+  fn next(&mut self) -> Option<K> {
+    self.iter.next().map(|(k, _)| k)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.iter.size_hint()
+  }
+}
+
+impl<K> DoubleEndedIterator for IntoIter<K> {
+  fn next_back(&mut self) -> Option<K> {
+    self.iter.next_back().map(|(k, _)| k)
+  }
+}
+
+impl<K> ExactSizeIterator for IntoIter<K> {}
+
+impl<K> FusedIterator for IntoIter<K> {}
+
+impl<'a, K> Clone for Range<'a, K> {
+  fn clone(&self) -> Self {
+    Range {
+      iter: self.iter.clone(),
+    }
+  }
+}
+
+impl<'a, K: 'a> Iterator for Range<'a, K> {
+  type Item = &'a K;
+
+  fn next(&mut self) -> Option<&'a K> {
+    self.iter.next().map(|(k, _)| k)
+  }
+}
+
+impl<'a, K: 'a> DoubleEndedIterator for Range<'a, K> {
+  fn next_back(&mut self) -> Option<&'a K> {
+    self.iter.next_back().map(|(k, _)| k)
+  }
+}
+
+impl<'a, K: 'a> FusedIterator for Range<'a, K> {}
+
+/// Sorted merge of two sets, used by set operations.
+struct MergeIter<'a, K: ARTKey + 'a> {
+  a: MergeSide<'a, K>,
+  b: MergeSide<'a, K>,
+}
+
+/// One side of a merge, which keeps encoded bytes of its next element, so that each element is
+/// encoded only once.
+struct MergeSide<'a, K: ARTKey + 'a> {
+  iter: Iter<'a, K>,
+  head: Option<(&'a K, K::Bytes<'a>)>,
+}
+
+impl<'a, K: ARTKey + 'a> MergeIter<'a, K> {
+  fn new(a: &'a ARTSet<K>, b: &'a ARTSet<K>) -> Self {
+    Self {
+      a: MergeSide::new(a.iter()),
+      b: MergeSide::new(b.iter()),
+    }
+  }
+
+  /// Compares next elements of both sides by their key bytes. `None` stands for an exhausted
+  /// side, which is ordered after any element.
+  fn peek_cmp(&self) -> Option<Ordering> {
+    match (&self.a.head, &self.b.head) {
+      (None, None) => None,
+      (Some(_), None) => Some(Ordering::Less),
+      (None, Some(_)) => Some(Ordering::Greater),
+      (Some((_, a)), Some((_, b))) => Some(a.as_ref().cmp(b.as_ref())),
+    }
+  }
+
+  fn lens(&self) -> (usize, usize) {
+    (self.a.len(), self.b.len())
+  }
+}
+
+impl<'a, K: ARTKey + 'a> Clone for MergeIter<'a, K> {
+  fn clone(&self) -> Self {
+    Self {
+      a: self.a.clone(),
+      b: self.b.clone(),
+    }
+  }
+}
+
+impl<'a, K: ARTKey + 'a> Clone for MergeSide<'a, K> {
+  /// Encodes the head again, since encoded bytes may not be cloneable.
+  fn clone(&self) -> Self {
+    Self {
+      iter: self.iter.clone(),
+      head: Self::encode(self.peek()),
+    }
+  }
+}
+
+impl<'a, K: ARTKey + 'a> MergeSide<'a, K> {
+  fn new(mut iter: Iter<'a, K>) -> Self {
+    let head = Self::encode(iter.next());
+    Self { iter, head }
+  }
+
+  fn encode(elem: Option<&'a K>) -> Option<(&'a K, K::Bytes<'a>)> {
+    elem.map(|elem| (elem, elem.to_bytes()))
+  }
+
+  fn peek(&self) -> Option<&'a K> {
+    self.head.as_ref().map(|(elem, _)| *elem)
+  }
+
+  fn next(&mut self) -> Option<&'a K> {
+    let next = Self::encode(self.iter.next());
+    mem::replace(&mut self.head, next).map(|(elem, _)| elem)
+  }
+
+  fn len(&self) -> usize {
+    self.iter.len() + self.head.is_some() as usize
+  }
+}
+
+/// Formats remaining elements of a cloned iterator as a list.
+struct DebugIter<I>(I);
+
+impl<I: Iterator + Clone> Debug for DebugIter<I>
+where
+  I::Item: Debug,
+{
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_list().entries(self.0.clone()).finish()
+  }
+}
+
+/// A lazy iterator producing elements in the union of `ARTSet`s.
+pub struct Union<'a, K: ARTKey + 'a>(MergeIter<'a, K>);
+
+/// A lazy iterator producing elements in the intersection of `ARTSet`s.
+pub struct Intersection<'a, K: ARTKey + 'a>(MergeIter<'a, K>);
+
+/// A lazy iterator producing elements in the difference of `ARTSet`s.
+pub struct Difference<'a, K: ARTKey + 'a>(MergeIter<'a, K>);
+
+/// A lazy iterator producing elements in the symmetric difference of `ARTSet`s.
+pub struct SymmetricDifference<'a, K: ARTKey + 'a>(MergeIter<'a, K>);
+
+impl<'a, K: ARTKey + 'a> Clone for Union<'a, K> {
+  fn clone(&self) -> Self {
+    Union(self.0.clone())
+  }
+}
+
+impl<'a, K: ARTKey + Debug + 'a> Debug for Union<'a, K> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("Union")
+      .field(&DebugIter(self.clone()))
+      .finish()
+  }
+}
+
+impl<'a, K: ARTKey + 'a> Clone for Intersection<'a, K> {
+  fn clone(&self) -> Self {
+    Intersection(self.0.clone())
+  }
+}
+
+impl<'a, K: ARTKey + Debug + 'a> Debug for Intersection<'a, K> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("Intersection")
+      .field(&DebugIter(self.clone()))
+      .finish()
+  }
+}
+
+impl<'a, K: ARTKey + 'a> Clone for Difference<'a, K> {
+  fn clone(&self) -> Self {
+    Difference(self.0.clone())
+  }
+}
+
+impl<'a, K: ARTKey + Debug + 'a> Debug for Difference<'a, K> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("Difference")
+      .field(&DebugIter(self.clone()))
+      .finish()
+  }
+}
+
+impl<'a, K: ARTKey + 'a> Clone for SymmetricDifference<'a, K> {
+  fn clone(&self) -> Self {
+    SymmetricDifference(self.0.clone())
+  }
+}
+
+impl<'a, K: ARTKey + Debug + 'a> Debug for SymmetricDifference<'a, K> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("SymmetricDifference")
+      .field(&DebugIter(self.clone()))
+      .finish()
+  }
+}
+
+impl<'a, K: ARTKey + 'a> Iterator for Union<'a, K> {
+  type Item = &'a K;
+
+  fn next(&mut self) -> Option<&'a K> {
+    match self.0.peek_cmp()? {
+      Ordering::Less => self.0.a.next(),
+      Ordering::Greater => self.0.b.next(),
+      Ordering::Equal => {
+        self.0.b.next();
+        self.0.a.next()
+      }
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let (a_len, b_len) = self.0.lens();
+    (a_len.max(b_len), Some(a_len + b_len))
+  }
+}
+
+impl<'a, K: ARTKey + 'a> Iterator for Intersection<'a, K> {
+  type Item = &'a K;
+
+  fn next(&mut self) -> Option<&'a K> {
+    loop {
+      self.0.a.peek()?;
+      self.0.b.peek()?;
+      match self.0.peek_cmp()? {
+        Ordering::Less => {
+          self.0.a.next();
+        }
+        Ordering::Greater => {
+          self.0.b.next();
+        }
+        Ordering::Equal => {
+          self.0.b.next();
+          return self.0.a.next();
+        }
+      }
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let (a_len, b_len) = self.0.lens();
+    (0, Some(a_len.min(b_len)))
+  }
+}
+
+impl<'a, K: ARTKey + 'a> Iterator for Difference<'a, K> {
+  type Item = &'a K;
+
+  fn next(&mut self) -> Option<&'a K> {
+    loop {
+      self.0.a.peek()?;
+      match self.0.peek_cmp()? {
+        Ordering::Less => return self.0.a.next(),
+        Ordering::Greater => {
+          self.0.b.next();
+        }
+        Ordering::Equal => {
+          self.0.a.next();
+          self.0.b.next();
+        }
+      }
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let (a_len, b_len) = self.0.lens();
+    (a_len.saturating_sub(b_len), Some(a_len))
+  }
+}
+
+impl<'a, K: ARTKey + 'a> Iterator for SymmetricDifference<'a, K> {
+  type Item = &'a K;
+
+  fn next(&mut self) -> Option<&'a K> {
+    loop {
+      match self.0.peek_cmp()? {
+        Ordering::Less => return self.0.a.next(),
+        Ordering::Greater => return self.0.b.next(),
+        Ordering::Equal => {
+          self.0.a.next();
+          self.0.b.next();
+        }
+      }
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let (a_len, b_len) = self.0.lens();
+    (0, Some(a_len + b_len))
+  }
+}
+
+impl<'a, K: ARTKey + 'a> FusedIterator for Union<'a, K> {}
+
+impl<'a, K: ARTKey + 'a> FusedIterator for Intersection<'a, K> {}
+
+impl<'a, K: ARTKey + 'a> FusedIterator for Difference<'a, K> {}
+
+impl<'a, K: ARTKey + 'a> FusedIterator for SymmetricDifference<'a, K> {}
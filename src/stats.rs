@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::mem;
+
+use allocator_api2::alloc::Allocator;
+
+use crate::key::ARTKey;
+use crate::marker::{Immut, InternalOrLeaf};
+use crate::node::{LeafNode, NodeArena, NodeImpl, NodeRef, NodeType};
+
+/// Shape and memory statistics of an `ARTMap`, returned by [`ARTMap::stats`].
+///
+/// Memory numbers are estimated from sizes of nodes and lengths of encoded keys, and don't include
+/// other heap memory owned by keys and values, or allocator overhead.
+///
+/// [`ARTMap::stats`]: crate::map::ARTMap::stats
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ARTStats {
+  pub node4: NodeClassStats,
+  pub node16: NodeClassStats,
+  pub node48: NodeClassStats,
+  pub node256: NodeClassStats,
+  pub leaves: usize,
+  /// Number of internal nodes whose partial key is longer than its inline storage, so only a part
+  /// of it is stored, and the rest is read from leaves.
+  pub truncated_partial_keys: usize,
+  /// Histogram of partial key lengths of internal nodes, i.e. `partial_key_lens[&n]` is the
+  /// number of internal nodes with partial key of `n` bytes. Only lengths of some node are present,
+  /// since partial keys may be arbitrarily long.
+  pub partial_key_lens: BTreeMap<usize, usize>,
+  /// Histogram of leaf depths, i.e. `leaf_depths[n]` is the number of leaves with `n` internal
+  /// nodes above them.
+  pub leaf_depths: Vec<usize>,
+  /// Bytes used by internal nodes.
+  pub internal_node_bytes: usize,
+  /// Bytes used by leaf nodes, including keys and values stored in them.
+  pub leaf_node_bytes: usize,
+  /// Total length of encoded keys. For string and byte string keys, which are encoded as is, this
+  /// estimates their heap memory, which isn't included in `leaf_node_bytes`.
+  pub key_bytes: usize,
+  /// Bytes of values stored inline in leaves, which are already included in `leaf_node_bytes`.
+  pub inline_value_bytes: usize,
+  /// Bytes reserved by node slabs of the map, including slots freed or not used yet.
+  pub arena_bytes: usize,
+}
+
+/// Statistics of internal nodes of one class.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeClassStats {
+  pub count: usize,
+  /// Total number of children, excluding leaves stored in leaf slot.
+  pub children: usize,
+  /// Number of nodes with a leaf in leaf slot, i.e. some key ends at this node.
+  pub leaf_slots: usize,
+  /// Bytes used by nodes.
+  pub bytes: usize,
+}
+
+impl NodeClassStats {
+  /// Returns average number of children per node, excluding leaf slot.
+  pub fn average_fan_out(&self) -> f64 {
+    if self.count == 0 {
+      0.0
+    } else {
+      self.children as f64 / self.count as f64
+    }
+  }
+}
+
+impl ARTStats {
+  pub(crate) fn new<K: ARTKey, V, A: Allocator>(
+    root: Option<NodeRef<Immut<'_>, K, V, InternalOrLeaf>>,
+    arena: &NodeArena<K, V, A>,
+  ) -> Self {
+    let mut stats = Self {
+      arena_bytes: arena.reserved_bytes(),
+      ..Self::default()
+    };
+    let mut stack: Vec<_> = root.map(|root| (root, 0)).into_iter().collect();
+    while let Some((node, depth)) = stack.pop() {
+      match node.downcast() {
+        NodeImpl::Leaf(leaf) => {
+          stats.leaves += 1;
+          stats.key_bytes += leaf.key_bytes().as_ref().len();
+          add_to_histogram(&mut stats.leaf_depths, depth);
+        }
+        NodeImpl::Internal(internal) => {
+          let class = match internal.node_type() {
+            NodeType::Node4 => &mut stats.node4,
+            NodeType::Node16 => &mut stats.node16,
+            NodeType::Node48 => &mut stats.node48,
+            NodeType::Node256 => &mut stats.node256,
+            NodeType::Leaf => unreachable!("This should not happen!"),
+          };
+          let bytes = internal.node_size();
+          if internal.is_partial_key_truncated() {
+            stats.truncated_partial_keys += 1;
+          }
+          class.count += 1;
+          class.children += internal.children_count();
+          class.bytes += bytes;
+          stats.internal_node_bytes += bytes;
+          *stats
+            .partial_key_lens
+            .entry(internal.partial_key_len())
+            .or_default() += 1;
+
+          if let Some(leaf) = internal.get_leaf() {
+            class.leaf_slots += 1;
+            stack.push((leaf.forget_type(), depth + 1));
+          }
+          internal.for_each_child(|_, child| stack.push((child, depth + 1)));
+        }
+      }
+    }
+
+    stats.leaf_node_bytes = stats.leaves * mem::size_of::<LeafNode<K, V>>();
+    stats.inline_value_bytes = stats.leaves * mem::size_of::<V>();
+    stats
+  }
+
+  /// Returns number of internal nodes.
+  pub fn internal_nodes(&self) -> usize {
+    self.node4.count + self.node16.count + self.node48.count + self.node256.count
+  }
+
+  /// Returns estimated bytes used by all nodes.
+  pub fn total_bytes(&self) -> usize {
+    self.internal_node_bytes + self.leaf_node_bytes
+  }
+}
+
+fn add_to_histogram(histogram: &mut Vec<usize>, value: usize) {
+  if histogram.len() <= value {
+    histogram.resize(value + 1, 0);
+  }
+  histogram[value] += 1;
+}
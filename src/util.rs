@@ -6,7 +6,7 @@ pub(crate) fn common_len(left: &[u8], right: &[u8]) -> usize {
     .zip(right)
     .position(|(left, right)| *left != *right)
   {
-    pos + 1
+    pos
   } else {
     min(left.len(), right.len())
   }
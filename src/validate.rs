@@ -0,0 +1,430 @@
+//! Structural invariant checks, enabled by the `debug-invariants` feature.
+
+use std::error::Error;
+use std::fmt;
+
+use allocator_api2::alloc::Allocator;
+
+use crate::key::ARTKey;
+use crate::map::ARTMap;
+use crate::marker::{Immut, Internal, InternalOrLeaf};
+use crate::node::{BoxedNode, NodeImpl, NodeRef, NodeType};
+
+/// Error returned by [`ARTMap::validate`], describing the first broken invariant found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvariantError {
+  /// Key bytes on the path from root to the broken node, excluding its own partial key.
+  pub path: Vec<u8>,
+  /// Class of the broken node, e.g. `"Node16"` or `"Leaf"`, or `"ARTMap"` for the map itself.
+  pub node: &'static str,
+  pub violation: Violation,
+}
+
+/// Invariant broken by a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+  /// Parent pointer doesn't point to the node holding this node.
+  Parent,
+  /// Recorded position in parent differs from the slot holding this node. `None` is the leaf
+  /// slot.
+  ChildPos {
+    expected: Option<u8>,
+    actual: Option<u8>,
+  },
+  /// Recorded children count differs from the number of children.
+  ChildrenCount { recorded: usize, actual: usize },
+  /// Number of children is out of bounds of the node class.
+  Capacity {
+    children: usize,
+    min: usize,
+    max: usize,
+  },
+  /// Internal node has less than two entries, including its leaf.
+  TooFewEntries { entries: usize },
+  /// Prefix length computed from parent pointers differs from the length of path.
+  PrefixLen { expected: usize, actual: usize },
+  /// Leaf key doesn't match the path to the leaf.
+  KeyMismatch,
+  /// Stored bytes of partial key differ from keys below the node, or no key below the node is
+  /// long enough to hold its partial key.
+  PartialKeyMismatch,
+  /// Node in leaf slot is not a leaf.
+  LeafSlot,
+  /// Recorded length of map differs from the number of leaves.
+  Length { recorded: usize, actual: usize },
+}
+
+impl fmt::Display for InvariantError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} at path {:?}: ", self.node, self.path)?;
+    match &self.violation {
+      Violation::Parent => write!(f, "parent pointer doesn't point to holder"),
+      Violation::ChildPos { expected, actual } => write!(
+        f,
+        "child position is {:?}, but it's held at {:?}",
+        actual, expected
+      ),
+      Violation::ChildrenCount { recorded, actual } => write!(
+        f,
+        "children count is {}, but it has {} children",
+        recorded, actual
+      ),
+      Violation::Capacity { children, min, max } => write!(
+        f,
+        "{} children is out of class bounds {}..={}",
+        children, min, max
+      ),
+      Violation::TooFewEntries { entries } => write!(f, "only {} entries", entries),
+      Violation::PrefixLen { expected, actual } => write!(
+        f,
+        "prefix length is {}, but path has {} bytes",
+        actual, expected
+      ),
+      Violation::KeyMismatch => write!(f, "key doesn't match path"),
+      Violation::PartialKeyMismatch => write!(f, "stored partial key doesn't match keys"),
+      Violation::LeafSlot => write!(f, "leaf slot holds an internal node"),
+      Violation::Length { recorded, actual } => {
+        write!(f, "length is {}, but it has {} leaves", recorded, actual)
+      }
+    }
+  }
+}
+
+impl Error for InvariantError {}
+
+/// Node to visit, with its expected parent and position.
+struct Visit<'a, K, V> {
+  node: NodeRef<Immut<'a>, K, V, InternalOrLeaf>,
+  parent: Option<(BoxedNode<K, V>, Option<u8>)>,
+  /// Length of parent's path with its partial key, which is a prefix of current path.
+  path_len: usize,
+}
+
+impl<K: ARTKey, V, A: Allocator> ARTMap<K, V, A> {
+  /// Walks the whole tree and checks its structural invariants, i.e. parent pointers and
+  /// positions, children counts and node class bounds, prefix lengths, stored partial keys, and
+  /// that every leaf key matches the path to it.
+  pub fn validate(&self) -> Result<(), InvariantError> {
+    let mut leaves = 0;
+    let mut path = Vec::new();
+    let mut stack: Vec<_> = self
+      .root_node_ref()
+      .map(|node| Visit {
+        node,
+        parent: None,
+        path_len: 0,
+      })
+      .into_iter()
+      .collect();
+
+    while let Some(Visit {
+      node,
+      parent,
+      path_len,
+    }) = stack.pop()
+    {
+      path.truncate(path_len);
+      if let Some((_, Some(k))) = parent {
+        path.push(k);
+      }
+      let name = node_name(&node);
+      let error = |path: &[u8], violation| InvariantError {
+        path: path.to_vec(),
+        node: name,
+        violation,
+      };
+
+      let actual_parent = node.ascend().map(|parent| parent.get_inner());
+      if actual_parent != parent.map(|(ptr, _)| ptr) {
+        return Err(error(&path, Violation::Parent));
+      }
+      if let Some((_, expected)) = parent {
+        let actual = node.child_pos().and_then(|pos| pos.to_idx());
+        if actual != expected {
+          return Err(error(&path, Violation::ChildPos { expected, actual }));
+        }
+      }
+      let prefix_len =
+        NodeRef::<Immut<'_>, K, V, InternalOrLeaf>::from_node_ptr(node.get_inner()).prefix_len();
+      if prefix_len != path.len() || node.prefix_len() != path.len() {
+        return Err(error(
+          &path,
+          Violation::PrefixLen {
+            expected: path.len(),
+            actual: prefix_len,
+          },
+        ));
+      }
+
+      match node.downcast() {
+        NodeImpl::Leaf(leaf) => {
+          let key = leaf.key_bytes();
+          let key = key.as_ref();
+          let matches = match parent {
+            Some((_, None)) => key == path.as_slice(),
+            _ => key.starts_with(&path),
+          };
+          if !matches {
+            return Err(error(&path, Violation::KeyMismatch));
+          }
+          leaves += 1;
+        }
+        NodeImpl::Internal(internal) => {
+          let mut children = Vec::new();
+          internal.for_each_child(|k, child| children.push((k, child)));
+          let recorded = internal.children_count();
+          if recorded != children.len() {
+            return Err(error(
+              &path,
+              Violation::ChildrenCount {
+                recorded,
+                actual: children.len(),
+              },
+            ));
+          }
+          let (min, max) = internal.children_count_bounds();
+          if children.len() < min || children.len() > max {
+            return Err(error(
+              &path,
+              Violation::Capacity {
+                children: children.len(),
+                min,
+                max,
+              },
+            ));
+          }
+          let leaf = internal.get_leaf();
+          let entries = children.len() + leaf.is_some() as usize;
+          if entries < 2 {
+            return Err(error(&path, Violation::TooFewEntries { entries }));
+          }
+
+          // Truncated partial key is read from a leaf, whose key is checked against the path
+          // when it's visited.
+          match read_partial_key(internal) {
+            Some(partial_key) if partial_key.starts_with(internal.stored_partial_key()) => {
+              path.extend_from_slice(&partial_key)
+            }
+            _ => return Err(error(&path, Violation::PartialKeyMismatch)),
+          }
+          let parent = Some(internal.get_inner());
+          // Push in reverse order, so that children are visited in key order.
+          for (k, child) in children.into_iter().rev() {
+            stack.push(Visit {
+              node: child,
+              parent: parent.map(|ptr| (ptr, Some(k))),
+              path_len: path.len(),
+            });
+          }
+          if let Some(leaf) = leaf {
+            let leaf = leaf.forget_type();
+            if !matches!(leaf.downcast(), NodeImpl::Leaf(_)) {
+              return Err(error(&path, Violation::LeafSlot));
+            }
+            stack.push(Visit {
+              node: leaf,
+              parent: parent.map(|ptr| (ptr, None)),
+              path_len: path.len(),
+            });
+          }
+        }
+      }
+    }
+
+    if leaves != self.length {
+      return Err(InvariantError {
+        path: Vec::new(),
+        node: "ARTMap",
+        violation: Violation::Length {
+          recorded: self.length,
+          actual: leaves,
+        },
+      });
+    }
+    Ok(())
+  }
+}
+
+/// Reads partial key of `node` like `with_partial_key`, but returns `None` instead of panicking
+/// when there is no leaf below it or the key of the minimum leaf is too short.
+fn read_partial_key<K: ARTKey, V>(node: NodeRef<Immut<'_>, K, V, Internal>) -> Option<Vec<u8>> {
+  if !node.is_partial_key_truncated() {
+    return Some(node.stored_partial_key().to_vec());
+  }
+  let start = node.prefix_len();
+  let end = start + node.partial_key_len();
+  let mut cur = node;
+  loop {
+    let next = match cur.get_leaf() {
+      Some(leaf) => leaf.forget_type(),
+      None => cur.next_child(0)?.1,
+    };
+    match next.downcast() {
+      NodeImpl::Leaf(leaf) => return leaf.key_bytes().as_ref().get(start..end).map(<[u8]>::to_vec),
+      NodeImpl::Internal(internal) => cur = internal,
+    }
+  }
+}
+
+fn node_name<K, V>(node: &NodeRef<Immut<'_>, K, V, InternalOrLeaf>) -> &'static str {
+  match node.downcast() {
+    NodeImpl::Leaf(_) => "Leaf",
+    NodeImpl::Internal(internal) => match internal.node_type() {
+      NodeType::Node4 => "Node4",
+      NodeType::Node16 => "Node16",
+      NodeType::Node48 => "Node48",
+      NodeType::Node256 => "Node256",
+      NodeType::Leaf => unreachable!("This should not happen!"),
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::marker::Mut;
+  use crate::node::PartialKey;
+
+  /// Map with root `Node4 { 'a' => Node4("b") { 'c', 'd' }, 'x' }`.
+  fn map() -> ARTMap<&'static str, u32> {
+    let mut map = ARTMap::new();
+    for (i, key) in ["abc", "abd", "x"].iter().enumerate() {
+      map.insert(*key, i as u32);
+    }
+    assert_eq!(map.validate(), Ok(()));
+    map
+  }
+
+  fn internal<'a>(
+    map: &'a mut ARTMap<&'static str, u32>,
+    path: &[u8],
+  ) -> NodeRef<Mut<'a>, &'static str, u32, Internal> {
+    let mut node = map.root_node_mut().unwrap();
+    for &k in path {
+      node = match node.downcast() {
+        NodeImpl::Internal(internal) => internal.find_child(k).unwrap(),
+        NodeImpl::Leaf(_) => panic!("no internal node at {:?}", path),
+      };
+    }
+    match node.downcast() {
+      NodeImpl::Internal(internal) => internal,
+      NodeImpl::Leaf(_) => panic!("no internal node at {:?}", path),
+    }
+  }
+
+  fn violation(map: &ARTMap<&'static str, u32>) -> (Vec<u8>, &'static str, Violation) {
+    let error = map.validate().unwrap_err();
+    (error.path, error.node, error.violation)
+  }
+
+  #[test]
+  fn length() {
+    let mut map = map();
+    map.length += 1;
+    assert_eq!(
+      violation(&map),
+      (
+        vec![],
+        "ARTMap",
+        Violation::Length {
+          recorded: 4,
+          actual: 3
+        }
+      )
+    );
+  }
+
+  #[test]
+  fn partial_key() {
+    let mut map = map();
+    // Partial key which is not truncated defines the path, so leaves below mismatch it.
+    internal(&mut map, b"a").set_partial_key(PartialKey::new(b"z"));
+    assert_eq!(
+      violation(&map),
+      (b"azc".to_vec(), "Leaf", Violation::KeyMismatch)
+    );
+
+    // Truncated partial key longer than keys below the node.
+    internal(&mut map, b"a").set_partial_key(PartialKey::new(&[b'b'; 20]));
+    assert_eq!(
+      violation(&map),
+      (b"a".to_vec(), "Node4", Violation::PartialKeyMismatch)
+    );
+
+    internal(&mut map, b"a").set_partial_key(PartialKey::new(b"b"));
+    assert_eq!(map.validate(), Ok(()));
+  }
+
+  #[test]
+  fn key() {
+    let mut map = map();
+    match internal(&mut map, b"a").find_child(b'c').unwrap().downcast() {
+      NodeImpl::Leaf(mut leaf) => *leaf.as_leaf_mut().key_mut() = "abx",
+      NodeImpl::Internal(_) => unreachable!(),
+    }
+    assert_eq!(
+      violation(&map),
+      (b"abc".to_vec(), "Leaf", Violation::KeyMismatch)
+    );
+  }
+
+  #[test]
+  fn child_pos() {
+    let mut map = map();
+    let mut node = internal(&mut map, b"a");
+    let child = node.find_child(b'c').unwrap().get_inner();
+    // Child is held at both 'c' and 'e', but records only the latter.
+    unsafe { node.update_child_at(Some(b'e').into(), Some(child)) };
+    assert_eq!(
+      violation(&map),
+      (
+        b"abc".to_vec(),
+        "Leaf",
+        Violation::ChildPos {
+          expected: Some(b'c'),
+          actual: Some(b'e')
+        }
+      )
+    );
+
+    let mut node = internal(&mut map, b"a");
+    unsafe {
+      node.update_child_at(Some(b'e').into(), None);
+      node.update_child_at(Some(b'c').into(), Some(child));
+    }
+    assert_eq!(map.validate(), Ok(()));
+  }
+
+  #[test]
+  fn too_few_entries() {
+    let mut map = map();
+    let mut node = internal(&mut map, b"a");
+    let child = unsafe { node.update_child_at(Some(b'd').into(), None) }.unwrap();
+    assert_eq!(
+      violation(&map),
+      (
+        b"a".to_vec(),
+        "Node4",
+        Violation::TooFewEntries { entries: 1 }
+      )
+    );
+
+    unsafe { internal(&mut map, b"a").update_child_at(Some(b'd').into(), Some(child)) };
+    assert_eq!(map.validate(), Ok(()));
+  }
+
+  #[test]
+  fn leaf_slot() {
+    let mut map = map();
+    let child = internal(&mut map, b"a").get_inner();
+    unsafe { internal(&mut map, b"").set_leaf(child.cast()) };
+    assert_eq!(violation(&map), (vec![], "Node4", Violation::LeafSlot));
+
+    let mut root = internal(&mut map, b"");
+    unsafe {
+      root.update_child_at(None.into(), None);
+      root.update_child_at(Some(b'a').into(), Some(child));
+    }
+    assert_eq!(map.validate(), Ok(()));
+  }
+}
@@ -0,0 +1,95 @@
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+use std::alloc::Layout;
+use std::cell::Cell;
+use std::ops::Bound::Included;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use allocator_api2::alloc::{AllocError, Allocator, Global};
+
+use art_rs::map::ARTMap;
+
+/// Allocator counting its live bytes, backed by `Global`.
+#[derive(Clone, Default)]
+struct Counting(Rc<Cell<usize>>);
+
+impl Counting {
+  fn live(&self) -> usize {
+    self.0.get()
+  }
+}
+
+unsafe impl Allocator for Counting {
+  fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    let ptr = Global.allocate(layout)?;
+    self.0.set(self.0.get() + layout.size());
+    Ok(ptr)
+  }
+
+  unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+    self.0.set(self.0.get() - layout.size());
+    Global.deallocate(ptr, layout)
+  }
+}
+
+fn key(i: u32) -> String {
+  format!("key/{}/{}", i % 7, i)
+}
+
+#[test]
+fn nodes_from_allocator() {
+  let alloc = Counting::default();
+  let mut map = ARTMap::new_in(alloc.clone());
+  assert_eq!(alloc.live(), 0);
+
+  for i in 0..1000 {
+    map.insert(key(i), i);
+  }
+  assert!(alloc.live() > 0);
+  assert_eq!(alloc.live(), map.stats().arena_bytes);
+
+  for i in (0..1000).step_by(2) {
+    assert_eq!(map.remove(key(i).as_str()), Some(i));
+  }
+  *map.entry(key(1)).or_insert(0) += 1;
+  let mut cursor = map.lower_bound_mut(Included("key/3"));
+  assert_eq!(cursor.remove_current().map(|(_, v)| v % 7), Some(3));
+  assert_eq!(map.len(), 499);
+  assert_eq!(alloc.live(), map.stats().arena_bytes);
+
+  map.clear();
+  assert!(map.is_empty());
+  assert_eq!(alloc.live(), 0);
+  map.insert(key(0), 0);
+  assert!(alloc.live() > 0);
+  drop(map);
+  assert_eq!(alloc.live(), 0);
+}
+
+#[test]
+fn derived_maps_share_allocator() {
+  let alloc = Counting::default();
+  let mut map = ARTMap::new_in(alloc.clone());
+  map.extend((0..500).map(|i| (key(i), i)));
+
+  let cloned = map.clone();
+  assert!(cloned.iter().eq(map.iter()));
+  assert_eq!(
+    alloc.live(),
+    map.stats().arena_bytes + cloned.stats().arena_bytes
+  );
+  drop(cloned);
+
+  let mut sorted: Vec<_> = (500..600).map(|i| (key(i), i)).collect();
+  sorted.sort();
+  map.bulk_extend_sorted(sorted).unwrap();
+  assert_eq!(map.len(), 600);
+  assert_eq!(alloc.live(), map.stats().arena_bytes);
+
+  let mut iter = map.into_iter();
+  assert!(iter.next().is_some());
+  assert!(alloc.live() > 0);
+  drop(iter);
+  assert_eq!(alloc.live(), 0);
+}
@@ -0,0 +1,103 @@
+use std::cell::Cell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::rc::Rc;
+
+use art_rs::map::{ARTMap, ARTStats};
+
+thread_local! {
+  /// Number of clones allowed before cloning panics.
+  static CLONE_BUDGET: Cell<usize> = const { Cell::new(usize::MAX) };
+}
+
+/// Value counting its live instances.
+struct Counted(Rc<Cell<usize>>);
+
+impl Counted {
+  fn new(live: &Rc<Cell<usize>>) -> Self {
+    live.set(live.get() + 1);
+    Counted(live.clone())
+  }
+}
+
+impl Clone for Counted {
+  fn clone(&self) -> Self {
+    let budget = CLONE_BUDGET.with(|budget| budget.replace(budget.get().saturating_sub(1)));
+    if budget == 0 {
+      panic!("clone budget exhausted");
+    }
+    Counted::new(&self.0)
+  }
+}
+
+impl Drop for Counted {
+  fn drop(&mut self) {
+    self.0.set(self.0.get() - 1);
+  }
+}
+
+fn counted_map(live: &Rc<Cell<usize>>, len: u32) -> ARTMap<u32, Counted> {
+  (0..len).map(|i| (i * 7919, Counted::new(live))).collect()
+}
+
+#[test]
+fn drop_values() {
+  let live = Rc::new(Cell::new(0));
+
+  let mut map = counted_map(&live, 100);
+  assert_eq!(live.get(), 100);
+  map.clear();
+  assert_eq!(live.get(), 0);
+  assert!(map.is_empty());
+  map.insert(1, Counted::new(&live));
+  drop(map);
+  assert_eq!(live.get(), 0);
+
+  let mut iter = counted_map(&live, 100).into_iter();
+  iter.next();
+  iter.next_back();
+  assert_eq!(live.get(), 98);
+  drop(iter);
+  assert_eq!(live.get(), 0);
+}
+
+#[test]
+fn clone_panic() {
+  let live = Rc::new(Cell::new(0));
+  let map = counted_map(&live, 100);
+  CLONE_BUDGET.with(|budget| budget.set(50));
+  assert!(catch_unwind(AssertUnwindSafe(|| map.clone())).is_err());
+  assert_eq!(live.get(), 100);
+
+  CLONE_BUDGET.with(|budget| budget.set(usize::MAX));
+  let cloned = map.clone();
+  assert_eq!(live.get(), 200);
+  assert!(cloned.keys().eq(map.keys()));
+  // Slabs of the original map may have free slots left by node growth.
+  let shape = |stats: ARTStats| ARTStats {
+    arena_bytes: 0,
+    ..stats
+  };
+  assert_eq!(shape(cloned.stats()), shape(map.stats()));
+}
+
+#[test]
+fn reuse_freed_nodes() {
+  let mut map = ARTMap::new();
+  for i in 0..1000u32 {
+    map.insert(i, i);
+  }
+  let reserved = map.stats().arena_bytes;
+  assert!(reserved >= map.stats().total_bytes());
+
+  let rounds = if cfg!(miri) { 2 } else { 10 };
+  for round in 0..rounds {
+    for i in 0..1000u32 {
+      assert_eq!(map.remove(&i), Some(i + round));
+    }
+    assert!(map.is_empty());
+    for i in 0..1000u32 {
+      map.insert(i, i + round + 1);
+    }
+    assert_eq!(map.stats().arena_bytes, reserved);
+  }
+}
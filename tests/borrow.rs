@@ -0,0 +1,124 @@
+use std::ops::Bound::{Excluded, Included, Unbounded};
+
+use art_rs::map::ARTMap;
+use art_rs::set::ARTSet;
+
+fn words() -> Vec<String> {
+  [
+    "",
+    "a",
+    "ab",
+    "abc",
+    "abd",
+    "b",
+    "ba",
+    "long-key-with-a-shared-prefix",
+    "long-key-with",
+  ]
+  .iter()
+  .map(|w| w.to_string())
+  .collect()
+}
+
+#[test]
+fn string_by_str_and_bytes() {
+  let mut map: ARTMap<String, usize> = words().into_iter().map(|w| (w.clone(), w.len())).collect();
+  for w in words() {
+    assert_eq!(map.get(w.as_str()), Some(&w.len()));
+    assert_eq!(map.get(w.as_bytes()), Some(&w.len()));
+    assert_eq!(map[w.as_str()], w.len());
+    assert_eq!(map[w.as_bytes()], w.len());
+  }
+  assert_eq!(map.get("abe"), None);
+  assert_eq!(map.get(&b"long-key-with-a"[..]), None);
+  assert_eq!(map.get(b"ab"), Some(&2));
+
+  *map.get_mut("ab").unwrap() += 10;
+  *map.get_mut(&b"abc"[..]).unwrap() += 10;
+  assert_eq!(map.get(&"ab".to_string()), Some(&12));
+  assert!(map
+    .range::<str, _>((Excluded("a"), Included("abd")))
+    .map(|(k, _)| k.as_str())
+    .eq(["ab", "abc", "abd"]));
+  assert!(map
+    .range::<[u8], _>((Included(&b"b"[..]), Unbounded))
+    .map(|(k, _)| k.as_str())
+    .eq(["b", "ba", "long-key-with", "long-key-with-a-shared-prefix"]));
+  assert_eq!(
+    map.lower_bound(Excluded("abd")).key().map(String::as_str),
+    Some("b")
+  );
+  assert_eq!(
+    map
+      .upper_bound(Excluded(&b"b"[..]))
+      .key()
+      .map(String::as_str),
+    Some("abd")
+  );
+  assert_eq!(
+    map
+      .lower_bound_mut(Included("long-key-with"))
+      .key()
+      .map(String::as_str),
+    Some("long-key-with")
+  );
+
+  assert_eq!(map.remove("abc"), Some(13));
+  assert_eq!(map.remove_kv(&b"ab"[..]), Some(("ab".to_string(), 12)));
+  assert_eq!(map.remove(""), Some(0));
+  assert_eq!(map.remove(""), None);
+  assert_eq!(map.len(), words().len() - 3);
+}
+
+#[test]
+fn bytes_by_slice_and_array() {
+  let mut map: ARTMap<Vec<u8>, usize> = words()
+    .into_iter()
+    .map(|w| (w.clone().into_bytes(), w.len()))
+    .collect();
+  for w in words() {
+    assert_eq!(map.get(w.as_bytes()), Some(&w.len()));
+    assert_eq!(map.get(&w.clone().into_bytes()), Some(&w.len()));
+  }
+  assert_eq!(map.get(b"abd"), Some(&3));
+  assert_eq!(map.get(b"abe"), None);
+  assert_eq!(map[&b"ba"[..]], 2);
+  assert!(map
+    .range::<[u8], _>((Unbounded, Excluded(&b"ab"[..])))
+    .map(|(k, _)| k.as_slice())
+    .eq([&b""[..], b"a"]));
+  assert_eq!(map.remove(b"ba"), Some(2));
+
+  let mut arrays: ARTMap<[u8; 2], u8> = (0..=255).map(|i| ([i, !i], i)).collect();
+  assert_eq!(arrays.get(&[7, !7][..]), Some(&7));
+  assert_eq!(arrays.get(&[7, 7][..]), None);
+  assert_eq!(arrays.remove(&[9, !9][..]), Some(9));
+}
+
+#[test]
+fn boxed_and_referenced() {
+  let boxed: ARTMap<Box<str>, usize> = words()
+    .into_iter()
+    .map(|w| (w.clone().into_boxed_str(), w.len()))
+    .collect();
+  assert_eq!(boxed.get("abc"), Some(&3));
+  assert_eq!(boxed.get(&Box::from("ba")), Some(&2));
+
+  let words = words();
+  let referenced: ARTMap<&str, usize> = words.iter().map(|w| (w.as_str(), w.len())).collect();
+  assert_eq!(referenced.get("abd"), Some(&3));
+  assert_eq!(referenced.get(&"abd"), Some(&3));
+  assert_eq!(referenced.get("abx"), None);
+
+  let mut set: ARTSet<String> = words.iter().cloned().collect();
+  assert!(set.contains("ab"));
+  assert!(set.contains(&b"ab"[..]));
+  assert!(set.remove("ab"));
+  assert!(!set.contains("ab"));
+  assert!(set.range::<str, _>((Included("b"), Unbounded)).eq([
+    "b",
+    "ba",
+    "long-key-with",
+    "long-key-with-a-shared-prefix"
+  ]));
+}
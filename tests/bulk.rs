@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use proptest::collection::{btree_map, vec};
+use proptest::prelude::*;
+
+use art_rs::map::{ARTMap, BulkLoadError};
+
+type Map = ARTMap<Vec<u8>, u32>;
+
+fn assert_map(map: &Map, expected: &BTreeMap<Vec<u8>, u32>) {
+  #[cfg(feature = "debug-invariants")]
+  map.validate().unwrap();
+  assert_eq!(map.len(), expected.len());
+  assert!(map.iter().eq(expected.iter()));
+  for (k, v) in expected {
+    assert_eq!(map.get(k), Some(v));
+  }
+}
+
+/// Maximum number of entries.
+const MAX_LEN: usize = if cfg!(miri) { 20 } else { 100 };
+
+fn entries() -> impl Strategy<Value = BTreeMap<Vec<u8>, u32>> {
+  // Small alphabet, so keys share prefixes and are often prefixes of each other.
+  btree_map(vec(0..3u8, 0..6), any::<u32>(), 0..MAX_LEN)
+}
+
+proptest! {
+  #[test]
+  fn from_sorted(expected in entries()) {
+    let map = Map::from_sorted_iter(expected.clone()).unwrap();
+    assert_map(&map, &expected);
+  }
+
+  #[test]
+  fn extend_sorted(old in entries(), new in entries()) {
+    let mut map = Map::from_sorted_iter(old.clone()).unwrap();
+    let new: BTreeMap<_, _> = new.into_iter().filter(|(k, _)| !old.contains_key(k)).collect();
+    map.bulk_extend_sorted(new.clone()).unwrap();
+    let mut expected = old;
+    expected.extend(new);
+    assert_map(&map, &expected);
+  }
+
+  #[test]
+  fn extend_sorted_with(old in entries(), new in entries()) {
+    let mut map = Map::from_sorted_iter(old.clone()).unwrap();
+    map.bulk_extend_sorted_with(new.clone(), |_, old, new| *old ^= new).unwrap();
+    let mut expected = old;
+    for (k, v) in new {
+      *expected.entry(k).or_insert(0) ^= v;
+    }
+    assert_map(&map, &expected);
+  }
+}
+
+#[test]
+fn unsorted() {
+  let input = vec![(vec![1], 0), (vec![1, 2], 1), (vec![1, 1], 2), (vec![3], 3)];
+  assert_eq!(
+    Map::from_sorted_iter(input.clone()).unwrap_err(),
+    BulkLoadError::Unsorted { index: 2 }
+  );
+  assert_eq!(
+    Map::from_sorted_iter_with(input.clone(), |_, _, _| {}).unwrap_err(),
+    BulkLoadError::Unsorted { index: 2 }
+  );
+
+  // Entries before the erroneous one are kept.
+  let mut map = Map::from_sorted_iter(vec![(vec![0], 10), (vec![2], 12)]).unwrap();
+  assert_eq!(
+    map.bulk_extend_sorted(input),
+    Err(BulkLoadError::Unsorted { index: 2 })
+  );
+  let expected = vec![(vec![0], 10), (vec![1], 0), (vec![1, 2], 1), (vec![2], 12)];
+  assert_map(&map, &expected.into_iter().collect());
+}
+
+#[test]
+fn duplicate() {
+  let input = vec![(vec![1], 0), (vec![1], 1), (vec![2], 2)];
+  assert_eq!(
+    Map::from_sorted_iter(input.clone()).unwrap_err(),
+    BulkLoadError::Duplicate { index: 1 }
+  );
+
+  // Key already in the map.
+  let mut map = Map::from_sorted_iter(vec![(vec![0], 10), (vec![2], 12)]).unwrap();
+  assert_eq!(
+    map.bulk_extend_sorted(vec![(vec![1], 1), (vec![2], 2), (vec![3], 3)]),
+    Err(BulkLoadError::Duplicate { index: 1 })
+  );
+  let expected = vec![(vec![0], 10), (vec![1], 1), (vec![2], 12)];
+  assert_map(&map, &expected.into_iter().collect());
+}
+
+#[test]
+fn resolve() {
+  // Consecutive duplicates are resolved in input order against the kept value.
+  let input = vec![(vec![1], 1), (vec![1], 2), (vec![1], 3), (vec![2], 4)];
+  let mut calls = Vec::new();
+  let map = Map::from_sorted_iter_with(input, |k, old, new| {
+    calls.push((k.clone(), *old, new));
+    *old = *old * 10 + new;
+  })
+  .unwrap();
+  assert_eq!(calls, vec![(vec![1], 1, 2), (vec![1], 12, 3)]);
+  assert_map(
+    &map,
+    &vec![(vec![1], 123), (vec![2], 4)].into_iter().collect(),
+  );
+
+  // Existing entry is the one kept.
+  let mut map = Map::from_sorted_iter(vec![(vec![1], 5), (vec![3], 6)]).unwrap();
+  map
+    .bulk_extend_sorted_with(vec![(vec![1], 7), (vec![2], 8)], |_, old, new| {
+      *old = *old * 10 + new
+    })
+    .unwrap();
+  let expected = vec![(vec![1], 57), (vec![2], 8), (vec![3], 6)];
+  assert_map(&map, &expected.into_iter().collect());
+}
+
+#[test]
+fn long_partial_key() {
+  // Keys sharing prefixes longer than partial keys stored in nodes.
+  let mut expected = BTreeMap::new();
+  for (i, len) in [0usize, 1, 15, 16, 17, 40, 100].iter().enumerate() {
+    let mut key = vec![7; *len];
+    expected.insert(key.clone(), i as u32);
+    key.push(1);
+    expected.insert(key.clone(), 100 + i as u32);
+    key.extend(vec![9; 30]);
+    expected.insert(key, 200 + i as u32);
+  }
+  let map = Map::from_sorted_iter(expected.clone()).unwrap();
+  assert_map(&map, &expected);
+
+  let (odd, even): (BTreeMap<_, _>, BTreeMap<_, _>) =
+    expected.clone().into_iter().partition(|(_, v)| v % 2 == 1);
+  let mut map = Map::from_sorted_iter(odd).unwrap();
+  map.bulk_extend_sorted(even).unwrap();
+  assert_map(&map, &expected);
+}
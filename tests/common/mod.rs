@@ -0,0 +1,230 @@
+//! Model of `ARTMap` operations checked against `BTreeMap`, shared by property tests and fuzz
+//! targets.
+
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use art_rs::map::{ARTMap, Entry};
+
+/// Fan-outs around node class boundaries.
+const FAN_OUTS: [usize; 7] = [4, 5, 16, 17, 48, 49, 256];
+
+/// Seed of a key. Seeds are mapped to keys biased towards shapes stressing the tree.
+#[derive(Debug, Clone, Copy)]
+pub struct KeySeed {
+  pub kind: u8,
+  pub a: u8,
+  pub b: u8,
+}
+
+#[derive(Debug, Clone)]
+pub enum Op {
+  Insert(KeySeed, u32),
+  /// Inserts `count` consecutive keys after the seed, to fill nodes up to their fan-out.
+  InsertMany(KeySeed, u8, u32),
+  EntryOrInsert(KeySeed, u32),
+  EntryAndModify(KeySeed, u32),
+  EntryRemove(KeySeed),
+  Get(KeySeed),
+  GetMut(KeySeed, u32),
+  Remove(KeySeed),
+  RemoveKv(KeySeed),
+  Range(KeySeed, KeySeed, u8),
+  Iter,
+}
+
+impl KeySeed {
+  pub fn key(self) -> Vec<u8> {
+    let KeySeed { kind, a, b } = self;
+    match kind % 5 {
+      // Keys being prefixes of each other, including the empty key.
+      0 => b"prefix"[..(a % 7) as usize].to_vec(),
+      // Long shared prefixes, so partial keys exceed `MAX_PREFIX_LEN`, possibly diverging at bytes
+      // which are not stored.
+      1 => {
+        let mut key = vec![b'L'; 12 + (a % 24) as usize];
+        if b & 2 == 2 {
+          let i = (b >> 2) as usize % key.len();
+          key[i] = b'M';
+        }
+        if b & 1 == 1 {
+          key.push(b >> 2);
+        }
+        key
+      }
+      // Children of node `F n` are bounded by `FAN_OUTS[n]`.
+      2 => {
+        let n = b as usize % FAN_OUTS.len();
+        vec![b'F', n as u8, (a as usize % FAN_OUTS[n]) as u8]
+      }
+      // Dense keys over a small alphabet, sharing prefixes.
+      3 => {
+        let len = (b % 5) as usize;
+        (0..len).map(|i| (a >> i) & 1).collect()
+      }
+      _ => vec![a, b],
+    }
+  }
+
+  fn next(self, n: u8) -> Self {
+    KeySeed {
+      a: self.a.wrapping_add(n),
+      ..self
+    }
+  }
+}
+
+/// Decodes operations from raw bytes, e.g. fuzzer input.
+pub fn decode_ops(mut data: &[u8]) -> Vec<Op> {
+  let mut ops = Vec::new();
+  while let Some((&tag, rest)) = data.split_first() {
+    if rest.len() < 10 {
+      break;
+    }
+    let seed = KeySeed {
+      kind: rest[0],
+      a: rest[1],
+      b: rest[2],
+    };
+    let other = KeySeed {
+      kind: rest[3],
+      a: rest[4],
+      b: rest[5],
+    };
+    let value = u32::from_le_bytes([rest[6], rest[7], rest[8], rest[9]]);
+    ops.push(match tag % 11 {
+      0 => Op::Insert(seed, value),
+      1 => Op::InsertMany(seed, rest[3], value),
+      2 => Op::EntryOrInsert(seed, value),
+      3 => Op::EntryAndModify(seed, value),
+      4 => Op::EntryRemove(seed),
+      5 => Op::Get(seed),
+      6 => Op::GetMut(seed, value),
+      7 => Op::Remove(seed),
+      8 => Op::RemoveKv(seed),
+      9 => Op::Range(seed, other, rest[6]),
+      _ => Op::Iter,
+    });
+    data = &rest[10..];
+  }
+  ops
+}
+
+/// Applies `ops` to both `ARTMap` and `BTreeMap`, and asserts they agree.
+pub fn run(ops: &[Op]) {
+  let mut map = ARTMap::new();
+  let mut model = BTreeMap::new();
+  for op in ops {
+    apply(&mut map, &mut model, op);
+    assert_eq!(map.len(), model.len(), "{:?}", op);
+    #[cfg(feature = "debug-invariants")]
+    map.validate().unwrap();
+  }
+  check_iter(&map, &model);
+}
+
+fn apply(map: &mut ARTMap<Vec<u8>, u32>, model: &mut BTreeMap<Vec<u8>, u32>, op: &Op) {
+  match *op {
+    Op::Insert(seed, value) => {
+      let key = seed.key();
+      assert_eq!(map.insert(key.clone(), value), model.insert(key, value));
+    }
+    Op::InsertMany(seed, count, value) => {
+      for i in 0..count {
+        let key = seed.next(i).key();
+        assert_eq!(map.insert(key.clone(), value), model.insert(key, value));
+      }
+    }
+    Op::EntryOrInsert(seed, value) => {
+      let key = seed.key();
+      let expected = model.entry(key.clone()).or_insert(value);
+      let actual = map.entry(key).or_insert(value);
+      *expected = expected.wrapping_add(1);
+      *actual = actual.wrapping_add(1);
+      assert_eq!(actual, expected);
+    }
+    Op::EntryAndModify(seed, value) => {
+      let key = seed.key();
+      let expected = model
+        .entry(key.clone())
+        .and_modify(|v| *v ^= value)
+        .or_insert(value);
+      let actual = map.entry(key).and_modify(|v| *v ^= value).or_insert(value);
+      assert_eq!(actual, expected);
+    }
+    Op::EntryRemove(seed) => {
+      let key = seed.key();
+      let expected = match model.entry(key.clone()) {
+        std::collections::btree_map::Entry::Occupied(entry) => Some(entry.remove_entry()),
+        std::collections::btree_map::Entry::Vacant(_) => None,
+      };
+      let actual = match map.entry(key) {
+        Entry::Occupied(entry) => Some(entry.remove_entry()),
+        Entry::Vacant(_) => None,
+      };
+      assert_eq!(actual, expected);
+    }
+    Op::Get(seed) => {
+      let key = seed.key();
+      assert_eq!(map.get(key.as_slice()), model.get(&key));
+    }
+    Op::GetMut(seed, value) => {
+      let key = seed.key();
+      let expected = model.get_mut(&key).map(|v| {
+        *v = v.wrapping_mul(value);
+        *v
+      });
+      let actual = map.get_mut(key.as_slice()).map(|v| {
+        *v = v.wrapping_mul(value);
+        *v
+      });
+      assert_eq!(actual, expected);
+    }
+    Op::Remove(seed) => {
+      let key = seed.key();
+      assert_eq!(map.remove(key.as_slice()), model.remove(&key));
+    }
+    Op::RemoveKv(seed) => {
+      let key = seed.key();
+      assert_eq!(map.remove_kv(key.as_slice()), model.remove_entry(&key));
+    }
+    Op::Range(start, end, flags) => {
+      let (mut start, mut end) = (start.key(), end.key());
+      if start > end {
+        std::mem::swap(&mut start, &mut end);
+      }
+      fn bound(key: &[u8], flag: u8, exclusive: bool) -> Bound<&[u8]> {
+        match flag % 3 {
+          0 => Bound::Included(key),
+          1 if exclusive => Bound::Excluded(key),
+          1 => Bound::Included(key),
+          _ => Bound::Unbounded,
+        }
+      }
+      // Both bounds can't be excluded when they're equal.
+      let exclusive = start != end;
+      let slices = (
+        bound(&start, flags, exclusive),
+        bound(&end, flags / 3, exclusive),
+      );
+      assert!(map
+        .range::<[u8], _>(slices)
+        .eq(model.range::<[u8], _>(slices)));
+      assert!(map
+        .range::<[u8], _>(slices)
+        .rev()
+        .eq(model.range::<[u8], _>(slices).rev()));
+    }
+    Op::Iter => check_iter(map, model),
+  }
+}
+
+fn check_iter(map: &ARTMap<Vec<u8>, u32>, model: &BTreeMap<Vec<u8>, u32>) {
+  assert_eq!(map.iter().len(), model.len());
+  assert!(map.iter().eq(model.iter()));
+  assert!(map.iter().rev().eq(model.iter().rev()));
+  assert!(map.keys().eq(model.keys()));
+  assert!(map.values().eq(model.values()));
+  assert_eq!(map.first_key_value(), model.first_key_value());
+  assert_eq!(map.last_key_value(), model.last_key_value());
+}
@@ -0,0 +1,297 @@
+use std::collections::BTreeMap;
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use proptest::collection::{btree_map, vec};
+use proptest::prelude::*;
+
+use art_rs::map::{ARTMap, CursorMut};
+
+type Model = BTreeMap<Vec<u8>, u32>;
+
+/// Maximum number of entries and operations. Rejected inserts unwind, which is slow under Miri.
+const MAX_LEN: usize = if cfg!(miri) { 10 } else { 50 };
+
+fn key() -> impl Strategy<Value = Vec<u8>> + Clone {
+  // Short keys over a small alphabet, so inserted keys often split the current leaf.
+  vec(0..3u8, 0..5)
+}
+
+fn long_key() -> impl Strategy<Value = Vec<u8>> + Clone {
+  // Long shared prefix, so partial keys are truncated and inserted keys may diverge at bytes
+  // which are not stored in nodes.
+  (0..40usize, vec(0..3u8, 0..3)).prop_map(|(len, tail)| [vec![7; len], tail].concat())
+}
+
+fn bound(kind: u8, key: &[u8]) -> Bound<&[u8]> {
+  match kind % 3 {
+    0 => Included(key),
+    1 => Excluded(key),
+    _ => Unbounded,
+  }
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+  MoveNext,
+  MovePrev,
+  InsertAfter(Vec<u8>),
+  InsertBefore(Vec<u8>),
+  RemoveCurrent,
+  RemoveCurrentAndMoveBack,
+  SetValue(u32),
+}
+
+fn op(key: impl Strategy<Value = Vec<u8>> + Clone) -> impl Strategy<Value = Op> {
+  prop_oneof![
+    3 => Just(Op::MoveNext),
+    3 => Just(Op::MovePrev),
+    3 => key.clone().prop_map(Op::InsertAfter),
+    3 => key.prop_map(Op::InsertBefore),
+    1 => Just(Op::RemoveCurrent),
+    1 => Just(Op::RemoveCurrentAndMoveBack),
+    1 => any::<u32>().prop_map(Op::SetValue),
+  ]
+}
+
+fn next(model: &Model, current: &Option<Vec<u8>>) -> Option<Vec<u8>> {
+  match current {
+    Some(k) => model.range::<[u8], _>((Excluded(&k[..]), Unbounded)).next(),
+    None => model.iter().next(),
+  }
+  .map(|(k, _)| k.clone())
+}
+
+fn prev(model: &Model, current: &Option<Vec<u8>>) -> Option<Vec<u8>> {
+  match current {
+    Some(k) => model
+      .range::<[u8], _>((Unbounded, Excluded(&k[..])))
+      .next_back(),
+    None => model.iter().next_back(),
+  }
+  .map(|(k, _)| k.clone())
+}
+
+/// Applies `op` to both `cursor` and the model, and returns the new current key of the model.
+fn apply(
+  cursor: &mut CursorMut<'_, Vec<u8>, u32>,
+  model: &mut Model,
+  current: Option<Vec<u8>>,
+  op: Op,
+  value: u32,
+) -> Option<Vec<u8>> {
+  match op {
+    Op::MoveNext => {
+      cursor.move_next();
+      next(model, &current)
+    }
+    Op::MovePrev => {
+      cursor.move_prev();
+      prev(model, &current)
+    }
+    Op::InsertAfter(k) => {
+      let valid =
+        current.as_ref().is_none_or(|c| *c < k) && next(model, &current).is_none_or(|n| k < n);
+      let result = catch_unwind(AssertUnwindSafe(|| cursor.insert_after(k.clone(), value)));
+      assert_eq!(result.is_ok(), valid);
+      if valid {
+        model.insert(k, value);
+      }
+      current
+    }
+    Op::InsertBefore(k) => {
+      let valid =
+        current.as_ref().is_none_or(|c| k < *c) && prev(model, &current).is_none_or(|p| p < k);
+      let result = catch_unwind(AssertUnwindSafe(|| cursor.insert_before(k.clone(), value)));
+      assert_eq!(result.is_ok(), valid);
+      if valid {
+        model.insert(k, value);
+      }
+      current
+    }
+    Op::RemoveCurrent => {
+      let new_current = current.as_ref().and_then(|_| next(model, &current));
+      let expected = current.map(|k| model.remove_entry(&k).unwrap());
+      assert_eq!(cursor.remove_current(), expected);
+      new_current
+    }
+    Op::RemoveCurrentAndMoveBack => {
+      let new_current = current.as_ref().and_then(|_| prev(model, &current));
+      let expected = current.map(|k| model.remove_entry(&k).unwrap());
+      assert_eq!(cursor.remove_current_and_move_back(), expected);
+      new_current
+    }
+    Op::SetValue(v) => {
+      if let Some(value) = cursor.value_mut() {
+        *value = v;
+        *model.get_mut(current.as_ref().unwrap()).unwrap() = v;
+      }
+      current
+    }
+  }
+}
+
+fn check_bounds(model: &Model, k: &[u8], kind: u8) -> Result<(), TestCaseError> {
+  let mut map: ARTMap<_, _> = model.clone().into_iter().collect();
+  let bound = bound(kind, k);
+  let lower = model.range::<[u8], _>((bound, Unbounded)).next();
+  let upper = model.range::<[u8], _>((Unbounded, bound)).next_back();
+  prop_assert_eq!(map.lower_bound(bound).key_value(), lower);
+  prop_assert_eq!(map.upper_bound(bound).key_value(), upper);
+  prop_assert_eq!(
+    map.lower_bound_mut(bound).key().cloned(),
+    lower.map(|(k, _)| k.clone())
+  );
+  prop_assert_eq!(
+    map.upper_bound_mut(bound).key().cloned(),
+    upper.map(|(k, _)| k.clone())
+  );
+  Ok(())
+}
+
+fn check_edit(mut model: Model, start: &[u8], ops: Vec<Op>) -> Result<(), TestCaseError> {
+  let mut map: ARTMap<_, _> = model.clone().into_iter().collect();
+  let mut cursor = map.lower_bound_mut(Included(start));
+  let mut current = model
+    .range::<[u8], _>((Included(start), Unbounded))
+    .next()
+    .map(|(k, _)| k.clone());
+  for (i, op) in ops.into_iter().enumerate() {
+    current = apply(&mut cursor, &mut model, current, op, i as u32);
+    prop_assert_eq!(cursor.key(), current.as_ref());
+    let expected_next = next(&model, &current);
+    let expected_prev = prev(&model, &current);
+    prop_assert_eq!(cursor.peek_next().map(|(k, _)| k.clone()), expected_next);
+    prop_assert_eq!(cursor.peek_prev().map(|(k, _)| k.clone()), expected_prev);
+    prop_assert_eq!(cursor.as_cursor().key(), current.as_ref());
+  }
+
+  #[cfg(feature = "debug-invariants")]
+  map.validate().unwrap();
+  prop_assert!(map.iter().eq(model.iter()));
+  Ok(())
+}
+
+proptest! {
+  #[test]
+  fn bounds(model in btree_map(key(), any::<u32>(), 0..MAX_LEN), k in key(), kind in 0..3u8) {
+    check_bounds(&model, &k, kind)?;
+  }
+
+  #[test]
+  fn bounds_long_prefix(
+    model in btree_map(long_key(), any::<u32>(), 0..MAX_LEN),
+    k in long_key(),
+    kind in 0..3u8,
+  ) {
+    check_bounds(&model, &k, kind)?;
+  }
+
+  #[test]
+  fn edit(
+    model in btree_map(key(), any::<u32>(), 0..MAX_LEN),
+    start in key(),
+    ops in vec(op(key()), 0..MAX_LEN),
+  ) {
+    check_edit(model, &start, ops)?;
+  }
+
+  #[test]
+  fn edit_long_prefix(
+    model in btree_map(long_key(), any::<u32>(), 0..MAX_LEN),
+    start in long_key(),
+    ops in vec(op(long_key()), 0..MAX_LEN),
+  ) {
+    check_edit(model, &start, ops)?;
+  }
+}
+
+#[test]
+fn ghost() {
+  let mut map: ARTMap<u32, u32> = (0..100).map(|i| (i * 5 + 10, i)).collect();
+
+  let mut cursor = map.upper_bound(Unbounded);
+  assert_eq!(cursor.key(), Some(&505));
+  cursor.move_next();
+  assert_eq!(cursor.key_value(), None);
+  assert_eq!(cursor.peek_next(), Some((&10, &0)));
+  assert_eq!(cursor.peek_prev(), Some((&505, &99)));
+  cursor.move_next();
+  assert_eq!(cursor.key(), Some(&10));
+  cursor.move_prev();
+  assert_eq!(cursor.key(), None);
+  cursor.move_prev();
+  assert_eq!(cursor.key(), Some(&505));
+
+  // Inserting at the ghost appends before it, or prepends after it.
+  let mut cursor = map.lower_bound_mut(Excluded(&505));
+  assert_eq!(cursor.key(), None);
+  cursor.insert_before(600, 100);
+  cursor.insert_after(1, 101);
+  cursor.insert_after(0, 102);
+  assert_eq!(cursor.key(), None);
+  assert_eq!(cursor.peek_next(), Some((&0, &mut 102)));
+  assert_eq!(cursor.peek_prev(), Some((&600, &mut 100)));
+  cursor.move_prev();
+  assert_eq!(cursor.key(), Some(&600));
+  assert!(map.keys().take(3).copied().eq([0, 1, 10]));
+
+  let mut empty = ARTMap::<u32, u32>::new();
+  let mut cursor = empty.lower_bound_mut(Unbounded);
+  assert_eq!(cursor.remove_current(), None);
+  cursor.move_next();
+  assert_eq!(cursor.key(), None);
+  cursor.insert_after(1, 1);
+  cursor.move_next();
+  assert_eq!(cursor.key(), Some(&1));
+}
+
+#[test]
+fn remove() {
+  let mut map: ARTMap<u32, u32> = (0..10).map(|i| (i, i)).collect();
+  let mut cursor = map.lower_bound_mut(Included(&5));
+  assert_eq!(cursor.remove_current(), Some((5, 5)));
+  assert_eq!(cursor.key(), Some(&6));
+  assert_eq!(cursor.remove_current_and_move_back(), Some((6, 6)));
+  assert_eq!(cursor.key(), Some(&4));
+
+  let mut cursor = map.upper_bound_mut(Unbounded);
+  assert_eq!(cursor.remove_current(), Some((9, 9)));
+  assert_eq!(cursor.key(), None);
+  cursor.move_next();
+  assert_eq!(cursor.remove_current_and_move_back(), Some((0, 0)));
+  assert_eq!(cursor.key(), None);
+  assert!(map.keys().copied().eq([1, 2, 3, 4, 7, 8]));
+}
+
+fn cursor_at(map: &mut ARTMap<u32, u32>, k: u32) -> CursorMut<'_, u32, u32> {
+  map.lower_bound_mut(Included(&k))
+}
+
+#[test]
+#[should_panic(expected = "key must be ordered above the current element")]
+fn insert_after_current() {
+  let mut map: ARTMap<u32, u32> = (0..10).map(|i| (i * 2, i)).collect();
+  cursor_at(&mut map, 4).insert_after(4, 0);
+}
+
+#[test]
+#[should_panic(expected = "key must be ordered below the next element")]
+fn insert_after_next() {
+  let mut map: ARTMap<u32, u32> = (0..10).map(|i| (i * 2, i)).collect();
+  cursor_at(&mut map, 4).insert_after(7, 0);
+}
+
+#[test]
+#[should_panic(expected = "key must be ordered below the current element")]
+fn insert_before_current() {
+  let mut map: ARTMap<u32, u32> = (0..10).map(|i| (i * 2, i)).collect();
+  cursor_at(&mut map, 4).insert_before(5, 0);
+}
+
+#[test]
+#[should_panic(expected = "key must be ordered above the previous element")]
+fn insert_before_prev() {
+  let mut map: ARTMap<u32, u32> = (0..10).map(|i| (i * 2, i)).collect();
+  cursor_at(&mut map, 4).insert_before(2, 0);
+}
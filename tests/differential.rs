@@ -0,0 +1,43 @@
+mod common;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use common::{KeySeed, Op};
+
+/// Maximum number of operations, which Miri runs far fewer of.
+const MAX_OPS: usize = if cfg!(miri) { 40 } else { 400 };
+
+fn key_seed() -> impl Strategy<Value = KeySeed> {
+  (any::<u8>(), any::<u8>(), any::<u8>()).prop_map(|(kind, a, b)| KeySeed { kind, a, b })
+}
+
+fn op() -> impl Strategy<Value = Op> {
+  prop_oneof![
+    6 => (key_seed(), any::<u32>()).prop_map(|(key, value)| Op::Insert(key, value)),
+    1 => (key_seed(), any::<u8>(), any::<u32>())
+      .prop_map(|(key, count, value)| Op::InsertMany(key, count, value)),
+    2 => (key_seed(), any::<u32>()).prop_map(|(key, value)| Op::EntryOrInsert(key, value)),
+    2 => (key_seed(), any::<u32>()).prop_map(|(key, value)| Op::EntryAndModify(key, value)),
+    2 => key_seed().prop_map(Op::EntryRemove),
+    2 => key_seed().prop_map(Op::Get),
+    2 => (key_seed(), any::<u32>()).prop_map(|(key, value)| Op::GetMut(key, value)),
+    4 => key_seed().prop_map(Op::Remove),
+    2 => key_seed().prop_map(Op::RemoveKv),
+    1 => (key_seed(), key_seed(), any::<u8>())
+      .prop_map(|(start, end, flags)| Op::Range(start, end, flags)),
+    1 => Just(Op::Iter),
+  ]
+}
+
+proptest! {
+  #[test]
+  fn matches_btreemap(ops in vec(op(), 0..MAX_OPS)) {
+    common::run(&ops);
+  }
+
+  #[test]
+  fn matches_btreemap_raw(data in vec(any::<u8>(), 0..MAX_OPS * 10)) {
+    common::run(&common::decode_ops(&data));
+  }
+}
@@ -0,0 +1,132 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use art_rs::key::{ARTBorrow, ARTKey};
+use art_rs::map::ARTMap;
+
+/// Counter of live instances.
+#[derive(Default)]
+struct Live(Rc<Cell<usize>>);
+
+impl Live {
+  fn get(&self) -> usize {
+    self.0.get()
+  }
+
+  fn track(&self) -> Rc<Cell<usize>> {
+    self.0.set(self.0.get() + 1);
+    self.0.clone()
+  }
+}
+
+/// Spreads keys over several levels and node classes.
+fn key(i: u32) -> u32 {
+  i * 7919 % 100_000
+}
+
+struct Key(u32, Rc<Cell<usize>>);
+
+impl Drop for Key {
+  fn drop(&mut self) {
+    self.1.set(self.1.get() - 1);
+  }
+}
+
+impl ARTKey for Key {
+  type Bytes<'a> = [u8; 4];
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    self.0.to_be_bytes()
+  }
+}
+
+impl ARTBorrow<u32> for Key {}
+
+struct Value(Rc<Cell<usize>>);
+
+impl Drop for Value {
+  fn drop(&mut self) {
+    self.0.set(self.0.get() - 1);
+  }
+}
+
+struct Counters {
+  keys: Live,
+  values: Live,
+}
+
+impl Counters {
+  fn new() -> Self {
+    Counters {
+      keys: Live::default(),
+      values: Live::default(),
+    }
+  }
+
+  fn entry(&self, k: u32) -> (Key, Value) {
+    (Key(k, self.keys.track()), Value(self.values.track()))
+  }
+
+  fn map(&self, len: u32) -> ARTMap<Key, Value> {
+    (0..len).map(|i| self.entry(key(i))).collect()
+  }
+
+  fn live(&self) -> (usize, usize) {
+    (self.keys.get(), self.values.get())
+  }
+}
+
+#[test]
+fn drop_map() {
+  let counters = Counters::new();
+  for len in [0, 1, 2, 5, 17, 49, 1000] {
+    let map = counters.map(len);
+    assert_eq!(counters.live(), (len as usize, len as usize));
+    drop(map);
+    assert_eq!(counters.live(), (0, 0));
+  }
+}
+
+#[test]
+fn drop_removed() {
+  let counters = Counters::new();
+  let mut map = counters.map(1000);
+
+  assert!(map.remove(&key(1)).is_some());
+  assert_eq!(counters.live(), (999, 999));
+  let kv = map.remove_kv(&key(2));
+  assert_eq!(counters.live(), (999, 999));
+  drop(kv);
+  assert_eq!(counters.live(), (998, 998));
+  assert!(map.pop_first().is_some());
+  assert!(map.pop_last().is_some());
+  assert_eq!(counters.live(), (996, 996));
+
+  // Replacing a value keeps the stored key and drops the new one.
+  let (k, v) = counters.entry(key(3));
+  assert!(map.insert(k, v).is_some());
+  assert_eq!(counters.live(), (996, 996));
+
+  map.clear();
+  assert_eq!(counters.live(), (0, 0));
+  let (k, v) = counters.entry(0);
+  map.insert(k, v);
+  drop(map);
+  assert_eq!(counters.live(), (0, 0));
+}
+
+#[test]
+fn drop_into_iter() {
+  let counters = Counters::new();
+  let mut iter = counters.map(1000).into_iter();
+  for _ in 0..10 {
+    drop(iter.next());
+    drop(iter.next_back());
+  }
+  assert_eq!(counters.live(), (980, 980));
+  drop(iter);
+  assert_eq!(counters.live(), (0, 0));
+
+  assert_eq!(counters.map(1000).into_iter().count(), 1000);
+  assert_eq!(counters.live(), (0, 0));
+}
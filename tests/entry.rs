@@ -0,0 +1,143 @@
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use art_rs::key::ARTKey;
+use art_rs::map::{ARTMap, Entry};
+
+#[derive(Debug, Clone)]
+enum Op {
+  OrInsert(u8, u32),
+  OrInsertWithKey(u8),
+  OrDefault(u8),
+  AndModify(u8, u32),
+  InsertEntry(u8, u32),
+  Remove(u8),
+  RemoveEntry(u8),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+  prop_oneof![
+    (any::<u8>(), any::<u32>()).prop_map(|(k, v)| Op::OrInsert(k, v)),
+    any::<u8>().prop_map(Op::OrInsertWithKey),
+    any::<u8>().prop_map(Op::OrDefault),
+    (any::<u8>(), any::<u32>()).prop_map(|(k, v)| Op::AndModify(k, v)),
+    (any::<u8>(), any::<u32>()).prop_map(|(k, v)| Op::InsertEntry(k, v)),
+    any::<u8>().prop_map(Op::Remove),
+    any::<u8>().prop_map(Op::RemoveEntry),
+  ]
+}
+
+/// Spreads keys over a few levels, so entries are inserted at leaves and internal nodes.
+fn key(k: u8) -> Vec<u8> {
+  vec![b'k'; (k % 4) as usize]
+    .into_iter()
+    .chain([k / 4])
+    .collect()
+}
+
+const MAX_OPS: usize = if cfg!(miri) { 30 } else { 200 };
+
+proptest! {
+  #[test]
+  fn entry(ops in vec(op(), 0..MAX_OPS)) {
+    let mut map = ARTMap::new();
+    let mut expected = BTreeMap::new();
+    for op in ops {
+      match op {
+        Op::OrInsert(k, v) => {
+          prop_assert_eq!(map.entry(key(k)).or_insert(v), expected.entry(key(k)).or_insert(v));
+        }
+        Op::OrInsertWithKey(k) => {
+          let f = |k: &Vec<u8>| k.len() as u32;
+          prop_assert_eq!(
+            map.entry(key(k)).or_insert_with_key(f),
+            expected.entry(key(k)).or_insert_with_key(f)
+          );
+        }
+        Op::OrDefault(k) => {
+          prop_assert_eq!(map.entry(key(k)).or_default(), expected.entry(key(k)).or_default());
+        }
+        Op::AndModify(k, v) => {
+          let entry = map.entry(key(k)).and_modify(|e| *e ^= v);
+          prop_assert_eq!(entry.key(), &key(k));
+          prop_assert_eq!(matches!(entry, Entry::Occupied(_)), expected.contains_key(&key(k)));
+          expected.entry(key(k)).and_modify(|e| *e ^= v);
+        }
+        Op::InsertEntry(k, v) => {
+          let entry = map.entry(key(k)).insert_entry(v);
+          prop_assert_eq!((entry.key(), entry.get()), (&key(k), &v));
+          expected.insert(key(k), v);
+        }
+        Op::Remove(k) => match (map.entry(key(k)), expected.entry(key(k))) {
+          (Entry::Occupied(entry), btree_map::Entry::Occupied(expected)) => {
+            prop_assert_eq!(entry.remove(), expected.remove());
+          }
+          (Entry::Vacant(entry), btree_map::Entry::Vacant(_)) => {
+            prop_assert_eq!(entry.into_key(), key(k));
+          }
+          _ => prop_assert!(false, "entries differ for key {:?}", key(k)),
+        },
+        Op::RemoveEntry(k) => match (map.entry(key(k)), expected.entry(key(k))) {
+          (Entry::Occupied(entry), btree_map::Entry::Occupied(expected)) => {
+            prop_assert_eq!(entry.remove_entry(), expected.remove_entry());
+          }
+          (Entry::Vacant(_), btree_map::Entry::Vacant(_)) => {}
+          _ => prop_assert!(false, "entries differ for key {:?}", key(k)),
+        },
+      }
+      prop_assert_eq!(map.len(), expected.len());
+    }
+    #[cfg(feature = "debug-invariants")]
+    map.validate().unwrap();
+    prop_assert!(map.iter().eq(expected.iter()));
+  }
+}
+
+/// Key encoded by its number only, so keys with different tags are equal in the map.
+#[derive(Debug, PartialEq)]
+struct Tagged(u32, &'static str);
+
+impl ARTKey for Tagged {
+  type Bytes<'a> = [u8; 4];
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    self.0.to_be_bytes()
+  }
+}
+
+#[test]
+fn replace_key() {
+  let mut map = ARTMap::new();
+  map.insert(Tagged(1, "old"), 1);
+  map.insert(Tagged(2, "old"), 2);
+  match map.entry(Tagged(1, "new")) {
+    Entry::Occupied(entry) => assert_eq!(entry.replace_key(), Tagged(1, "old")),
+    Entry::Vacant(_) => unreachable!(),
+  }
+  assert_eq!(map.first_key_value(), Some((&Tagged(1, "new"), &1)));
+  // Inserting through an existing entry keeps the stored key.
+  assert_eq!(map.insert(Tagged(2, "new"), 3), Some(2));
+  assert_eq!(map.last_key_value(), Some((&Tagged(2, "old"), &3)));
+}
+
+#[test]
+#[should_panic(expected = "replace_key called on an entry not created by `ARTMap::entry`")]
+fn replace_key_without_key() {
+  let mut map = ARTMap::new();
+  map.insert(Tagged(1, "old"), 1);
+  map.first_entry().unwrap().replace_key();
+}
+
+#[test]
+fn remove_kv() {
+  let mut map: ARTMap<u32, u32> = (0..10).map(|i| (i, i)).collect();
+  assert_eq!(map.first_entry().unwrap().remove_kv(), (0, 0));
+  match map.entry(5) {
+    Entry::Occupied(entry) => assert_eq!(entry.remove_kv(), (5, 5)),
+    Entry::Vacant(_) => unreachable!(),
+  }
+  assert!(map.keys().copied().eq([1, 2, 3, 4, 6, 7, 8, 9]));
+}
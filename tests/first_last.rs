@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+
+use proptest::collection::{btree_map, vec};
+use proptest::prelude::*;
+
+use art_rs::map::ARTMap;
+
+proptest! {
+  #[test]
+  fn pop(
+    mut expected in btree_map(vec(0..3u8, 0..6), any::<u32>(), 0..100),
+    from_back in vec(any::<bool>(), 1..8),
+  ) {
+    let mut map: ARTMap<_, _> = expected.clone().into_iter().collect();
+    for &from_back in from_back.iter().cycle().take(expected.len() + 1) {
+      prop_assert_eq!(map.first_key_value(), expected.first_key_value());
+      prop_assert_eq!(map.last_key_value(), expected.last_key_value());
+      if from_back {
+        prop_assert_eq!(map.pop_last(), expected.pop_last());
+      } else {
+        prop_assert_eq!(map.pop_first(), expected.pop_first());
+      }
+      prop_assert_eq!(map.len(), expected.len());
+    }
+    prop_assert!(map.is_empty());
+    #[cfg(feature = "debug-invariants")]
+    map.validate().unwrap();
+  }
+}
+
+#[test]
+fn entries() {
+  let mut map: ARTMap<u32, u32> = (0..100).map(|i| (i * 7, i)).collect();
+  let mut expected: BTreeMap<u32, u32> = (0..100).map(|i| (i * 7, i)).collect();
+
+  *map.first_entry().unwrap().get_mut() += 100;
+  *expected.first_entry().unwrap().get_mut() += 100;
+  assert_eq!(map.last_entry().unwrap().insert(0), 99);
+  expected.last_entry().unwrap().insert(0);
+  assert_eq!(map.first_entry().unwrap().key(), &0);
+  assert_eq!(map.last_entry().unwrap().remove_entry(), (693, 0));
+  expected.last_entry().unwrap().remove_entry();
+  assert!(map.iter().eq(expected.iter()));
+
+  // Keys which are prefixes of others come first.
+  let mut map: ARTMap<&str, ()> = ["ab", "a", "abc", "", "b"]
+    .iter()
+    .map(|&k| (k, ()))
+    .collect();
+  assert_eq!(map.pop_first(), Some(("", ())));
+  assert_eq!(map.pop_first(), Some(("a", ())));
+  assert_eq!(map.pop_last(), Some(("b", ())));
+  assert_eq!(map.pop_last(), Some(("abc", ())));
+  assert_eq!(map.first_key_value(), map.last_key_value());
+  assert_eq!(map.pop_last(), Some(("ab", ())));
+
+  assert_eq!(map.first_key_value(), None);
+  assert_eq!(map.last_key_value(), None);
+  assert!(map.first_entry().is_none());
+  assert!(map.last_entry().is_none());
+  assert_eq!(map.pop_first(), None);
+  assert_eq!(map.pop_last(), None);
+}
@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use proptest::collection::{btree_map, vec};
+use proptest::prelude::*;
+
+use art_rs::map::ARTMap;
+
+/// Maximum number of entries.
+const MAX_LEN: usize = if cfg!(miri) { 30 } else { 200 };
+
+fn keys() -> impl Strategy<Value = BTreeMap<Vec<u8>, u32>> {
+  // Small alphabet, so keys share prefixes and are prefixes of each other.
+  btree_map(vec(0..4u8, 0..6), any::<u32>(), 0..MAX_LEN)
+}
+
+/// Takes items from both ends of `iter` as directed by `from_back`, checking its length.
+fn interleave<I: DoubleEndedIterator + ExactSizeIterator>(
+  mut iter: I,
+  from_back: &[bool],
+) -> Vec<I::Item> {
+  let mut front = vec![];
+  let mut back = vec![];
+  for &from_back in from_back.iter().cycle().take(iter.len() + 2) {
+    let len = iter.len();
+    assert_eq!(iter.size_hint(), (len, Some(len)));
+    let item = if from_back {
+      iter.next_back()
+    } else {
+      iter.next()
+    };
+    match item {
+      Some(item) if from_back => back.push(item),
+      Some(item) => front.push(item),
+      None => assert_eq!(len, 0),
+    }
+  }
+  assert!(iter.next().is_none());
+  assert!(iter.next_back().is_none());
+  front.extend(back.into_iter().rev());
+  front
+}
+
+proptest! {
+  #[test]
+  fn iter(expected in keys(), from_back in vec(any::<bool>(), 1..8)) {
+    let map: ARTMap<_, _> = expected.clone().into_iter().collect();
+    let expected_items: Vec<_> = expected.iter().collect();
+    prop_assert_eq!(interleave(map.iter(), &from_back), expected_items.clone());
+    prop_assert_eq!(interleave((&map).into_iter(), &from_back), expected_items);
+    prop_assert_eq!(
+      interleave(map.keys(), &from_back),
+      expected.keys().collect::<Vec<_>>()
+    );
+    prop_assert_eq!(
+      interleave(map.values(), &from_back),
+      expected.values().collect::<Vec<_>>()
+    );
+    prop_assert_eq!(
+      interleave(map.into_iter(), &from_back),
+      expected.into_iter().collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn iter_mut(mut expected in keys(), from_back in vec(any::<bool>(), 1..8)) {
+    let mut map: ARTMap<_, _> = expected.clone().into_iter().collect();
+    for (k, v) in interleave(map.iter_mut(), &from_back) {
+      *v = v.wrapping_add(k.len() as u32);
+    }
+    for v in interleave(map.values_mut(), &from_back) {
+      *v = v.wrapping_mul(3);
+    }
+    for v in interleave((&mut map).into_iter(), &from_back) {
+      *v.1 ^= 1;
+    }
+    for (k, v) in expected.iter_mut() {
+      *v = v.wrapping_add(k.len() as u32).wrapping_mul(3) ^ 1;
+    }
+    prop_assert!(map.iter().eq(expected.iter()));
+  }
+}
+
+#[test]
+fn into_iter_partial() {
+  let len = if cfg!(miri) { 100 } else { 1000 };
+  let map: ARTMap<_, _> = (0..len).map(|i| (i.to_string(), i)).collect();
+  let mut expected: Vec<_> = (0..len).map(|i| (i.to_string(), i)).collect();
+  expected.sort();
+
+  let mut iter = map.into_iter();
+  let last = expected.len() - 1;
+  assert_eq!(iter.next(), Some(expected[0].clone()));
+  assert_eq!(iter.next_back(), Some(expected[last].clone()));
+  assert!(iter.eq(expected[1..last].iter().cloned()));
+}
+
+#[test]
+fn empty() {
+  let mut map = ARTMap::<u32, u32>::new();
+  assert_eq!(map.iter().next(), None);
+  assert_eq!(map.iter().next_back(), None);
+  assert_eq!(map.iter_mut().len(), 0);
+  assert_eq!(map.keys().len(), 0);
+  assert_eq!(map.values_mut().next(), None);
+  assert_eq!(map.into_iter().next_back(), None);
+}
@@ -0,0 +1,223 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use proptest::prelude::*;
+
+use art_rs::key::ARTKey;
+use art_rs::map::ARTMap;
+
+fn byte_order<T: ARTKey + ?Sized>(a: &T, b: &T) -> Ordering {
+  a.to_bytes().as_ref().cmp(b.to_bytes().as_ref())
+}
+
+/// Checks that encoding of sorted `keys` is strictly increasing.
+fn assert_increasing<T: ARTKey + Debug>(keys: &[T]) {
+  for pair in keys.windows(2) {
+    assert_eq!(byte_order(&pair[0], &pair[1]), Ordering::Less, "{:?}", pair);
+  }
+}
+
+#[test]
+fn signed() {
+  assert_increasing(&[i8::MIN, -2, -1, 0, 1, i8::MAX]);
+  assert_increasing(&[i16::MIN, -256, -1, 0, 255, 256, i16::MAX]);
+  assert_increasing(&[i32::MIN, i32::MIN + 1, -65536, -1, 0, 1, 65536, i32::MAX]);
+  assert_increasing(&[i64::MIN, -1, 0, 1, i64::MAX]);
+  assert_increasing(&[i128::MIN, -1, 0, 1, i128::MAX]);
+  assert_increasing(&[isize::MIN, -1, 0, 1, isize::MAX]);
+  assert_eq!(0i32.to_bytes(), [0x80, 0, 0, 0]);
+  assert_eq!((-1i32).to_bytes(), [0x7F, 0xFF, 0xFF, 0xFF]);
+}
+
+#[test]
+fn unsigned() {
+  assert_increasing(&[0u8, 1, 0x7F, 0x80, u8::MAX]);
+  assert_increasing(&[0u16, 0xFF, 0x100, u16::MAX]);
+  assert_increasing(&[0u32, 0xFF, 0x100, 0x8000_0000, u32::MAX]);
+  assert_increasing(&[0u64, 1 << 63, u64::MAX]);
+  assert_increasing(&[0u128, 1 << 127, u128::MAX]);
+  assert_increasing(&[false, true]);
+}
+
+#[test]
+fn floats() {
+  let neg_nan = f64::from_bits(f64::NAN.to_bits() | (1 << 63));
+  assert_increasing(&[
+    neg_nan,
+    f64::NEG_INFINITY,
+    f64::MIN,
+    -1.0,
+    -f64::MIN_POSITIVE,
+    -0.0,
+    0.0,
+    f64::MIN_POSITIVE,
+    1.0,
+    f64::MAX,
+    f64::INFINITY,
+    f64::NAN.copysign(1.0),
+  ]);
+  let neg_nan = f32::from_bits(f32::NAN.to_bits() | (1 << 31));
+  assert_increasing(&[
+    neg_nan,
+    f32::NEG_INFINITY,
+    -1.0,
+    -0.0,
+    0.0,
+    1.0,
+    f32::INFINITY,
+    f32::NAN.copysign(1.0),
+  ]);
+
+  // -0.0 and 0.0 are distinct keys, and NaN can be looked up.
+  let mut map = ARTMap::new();
+  for k in [0.0, -0.0, f64::NAN, 1.5, -1.5] {
+    map.insert(k, k.to_bits());
+  }
+  assert_eq!(map.len(), 5);
+  assert_eq!(map.get(&-0.0), Some(&(-0.0f64).to_bits()));
+  assert_eq!(map.get(&f64::NAN), Some(&f64::NAN.to_bits()));
+  let keys: Vec<_> = map.keys().map(|k| k.to_bits()).collect();
+  let expected: Vec<_> = [-1.5, -0.0, 0.0, 1.5, f64::NAN]
+    .iter()
+    .map(|k: &f64| k.to_bits())
+    .collect();
+  assert_eq!(keys, expected);
+}
+
+#[test]
+fn chars() {
+  assert_increasing(&[
+    '\0',
+    'A',
+    'a',
+    '\u{7F}',
+    '\u{80}',
+    'é',
+    '\u{D7FF}',
+    '\u{E000}',
+    '\u{10FFFF}',
+  ]);
+  assert_eq!('a'.to_bytes(), [0, 0, 0, 0x61]);
+}
+
+#[test]
+fn strings() {
+  // Prefixes come first, and bytes compare unsigned.
+  assert_increasing(&["", "a", "a\0", "ab", "b", "é"]);
+  assert_eq!(byte_order("abc", "abc"), Ordering::Equal);
+  assert_eq!("abc".to_string().to_bytes(), "abc".to_bytes());
+  assert_eq!(b"abc".to_vec().to_bytes(), "abc".to_bytes());
+  assert_eq!(Box::<str>::from("abc").to_bytes(), "abc".to_bytes());
+
+  let mut map = ARTMap::new();
+  for s in ["", "a", "ab", "é"] {
+    map.insert(s.to_string(), s.len());
+  }
+  for s in ["", "a", "ab", "é"] {
+    assert_eq!(map.get(s), map.get(&s.to_string()));
+    assert_eq!(map.get(s), Some(&s.len()));
+  }
+}
+
+#[test]
+fn arrays() {
+  assert_increasing(&[[0u32, u32::MAX], [1, 0], [1, 1], [u32::MAX, 0]]);
+  assert_increasing(&[[-1i16, 5], [0, -5], [0, 0]]);
+  assert_increasing(&[[[1u8, 2], [0, 0]], [[1, 2], [0, 1]], [[2, 0], [0, 0]]]);
+  assert_eq!([1u16, 2].to_bytes().as_ref(), [0, 1, 0, 2]);
+  assert_eq!(b"ab".to_bytes().as_ref(), b"ab");
+
+  let map: ARTMap<[u32; 4], usize> = (0..100).map(|i| ([i % 3, 0, i, !i], i as usize)).collect();
+  assert_eq!(map.get(&[2, 0, 5, !5]), Some(&5));
+  assert!(map.keys().is_sorted());
+}
+
+#[test]
+fn shared_and_borrowed() {
+  assert_eq!(Rc::<str>::from("abc").to_bytes(), "abc".to_bytes());
+  assert_eq!(Arc::<[u8]>::from(&b"abc"[..]).to_bytes(), "abc".to_bytes());
+  assert_eq!(Cow::Borrowed("abc").to_bytes(), "abc".to_bytes());
+  assert_eq!(
+    Cow::<[u8]>::Owned(b"abc".to_vec()).to_bytes(),
+    "abc".to_bytes()
+  );
+
+  let mut map: ARTMap<Arc<str>, usize> = ARTMap::new();
+  for s in ["b", "a", "ab"] {
+    map.insert(Arc::from(s), s.len());
+  }
+  assert_eq!(map.get("ab"), Some(&2));
+  assert!(map.keys().map(|k| &**k).eq(["a", "ab", "b"]));
+
+  let mut map: ARTMap<Cow<'_, str>, usize> = ARTMap::new();
+  map.insert(Cow::Borrowed("a"), 1);
+  map.insert(Cow::Owned("ab".to_string()), 2);
+  assert_eq!(map.get("ab"), Some(&2));
+  assert_eq!(map.remove("a"), Some(1));
+
+  let map: ARTMap<Rc<[u8]>, usize> = vec![(Rc::from(&b"x"[..]), 1)].into_iter().collect();
+  assert_eq!(map.get(&b"x"[..]), Some(&1));
+}
+
+proptest! {
+  #[test]
+  fn order_u32_array(a: [u32; 4], b: [u32; 4]) {
+    prop_assert_eq!(byte_order(&a, &b), a.cmp(&b));
+  }
+
+  #[test]
+  fn order_i64(a: i64, b: i64) {
+    prop_assert_eq!(byte_order(&a, &b), a.cmp(&b));
+  }
+
+  #[test]
+  fn order_i16(a: i16, b: i16) {
+    prop_assert_eq!(byte_order(&a, &b), a.cmp(&b));
+  }
+
+  #[test]
+  fn order_u32(a: u32, b: u32) {
+    prop_assert_eq!(byte_order(&a, &b), a.cmp(&b));
+  }
+
+  #[test]
+  fn order_f64(a: f64, b: f64) {
+    prop_assert_eq!(byte_order(&a, &b), a.total_cmp(&b));
+  }
+
+  #[test]
+  fn order_f64_bits(a: u64, b: u64) {
+    // Covers NaNs with any payload and subnormals.
+    let (a, b) = (f64::from_bits(a), f64::from_bits(b));
+    prop_assert_eq!(byte_order(&a, &b), a.total_cmp(&b));
+  }
+
+  #[test]
+  fn order_f32_bits(a: u32, b: u32) {
+    let (a, b) = (f32::from_bits(a), f32::from_bits(b));
+    prop_assert_eq!(byte_order(&a, &b), a.total_cmp(&b));
+  }
+
+  #[test]
+  fn order_char(a: char, b: char) {
+    prop_assert_eq!(byte_order(&a, &b), a.cmp(&b));
+  }
+
+  #[test]
+  fn order_string(a: String, b: String) {
+    prop_assert_eq!(byte_order(&a, &b), a.cmp(&b));
+    prop_assert_eq!(byte_order(a.as_str(), b.as_str()), a.cmp(&b));
+  }
+
+  #[test]
+  fn map_order_i32(keys: Vec<i32>) {
+    let map: ARTMap<_, _> = keys.iter().map(|&k| (k, ())).collect();
+    let mut expected = keys;
+    expected.sort_unstable();
+    expected.dedup();
+    prop_assert!(map.keys().eq(expected.iter()));
+  }
+}
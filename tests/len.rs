@@ -0,0 +1,108 @@
+use std::cell::Cell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use art_rs::key::{ARTBorrow, ARTKey};
+use art_rs::map::ARTMap;
+
+/// Key whose encoding panics from the given call on.
+struct Fragile {
+  key: u32,
+  calls: Cell<usize>,
+  panic_at: usize,
+}
+
+impl Fragile {
+  fn new(key: u32) -> Self {
+    Self::panicking_at(key, usize::MAX)
+  }
+
+  fn panicking_at(key: u32, panic_at: usize) -> Self {
+    Fragile {
+      key,
+      calls: Cell::new(0),
+      panic_at,
+    }
+  }
+}
+
+impl ARTKey for Fragile {
+  type Bytes<'a> = [u8; 4];
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    self.calls.set(self.calls.get() + 1);
+    if self.calls.get() >= self.panic_at {
+      panic!("encoding key {}", self.key);
+    }
+    self.key.to_be_bytes()
+  }
+}
+
+impl ARTBorrow<u32> for Fragile {}
+
+#[test]
+fn len() {
+  let mut map = ARTMap::new();
+  assert_eq!(map.len(), 0);
+  assert!(map.is_empty());
+
+  for i in 0..100 {
+    assert_eq!(map.insert(Fragile::new(i * 31), i), None);
+    assert_eq!(map.len(), i as usize + 1);
+  }
+  assert_eq!(map.insert(Fragile::new(31), 0), Some(1));
+  *map.entry(Fragile::new(62)).or_insert(0) += 1;
+  assert_eq!(map.len(), 100);
+  assert!(!map.is_empty());
+
+  assert_eq!(map.remove(&31), Some(0));
+  assert_eq!(map.remove(&31), None);
+  assert!(map.pop_first().is_some());
+  assert_eq!(map.len(), 98);
+
+  map.clear();
+  assert_eq!(map.len(), 0);
+  assert!(map.is_empty());
+}
+
+#[test]
+fn len_after_panic() {
+  let mut map = ARTMap::new();
+  for i in 0..100 {
+    map.insert(Fragile::new(i * 31), i);
+  }
+
+  // Key is encoded once to find the entry, and again to link the leaf.
+  for k in [2, 0x1F01, 0x1F_0000, 31 * 200] {
+    let result = catch_unwind(AssertUnwindSafe(|| {
+      map.insert(Fragile::panicking_at(k, 2), 0)
+    }));
+    assert!(result.is_err());
+    assert_eq!(map.len(), 100);
+    assert_eq!(map.iter().count(), 100);
+    assert_eq!(map.get(&k), None);
+    #[cfg(feature = "debug-invariants")]
+    map.validate().unwrap();
+  }
+
+  map.insert(Fragile::new(1), 1);
+  assert_eq!(map.len(), 101);
+  assert_eq!(map.get(&1), Some(&1));
+}
+
+#[test]
+fn no_node_leaked_after_panic() {
+  // Root is a leaf, so inserting splits it with a new parent.
+  let mut map = ARTMap::new();
+  map.insert(Fragile::new(1), 1);
+  let reserved = map.stats().arena_bytes;
+
+  // More than the first slab chunk holds, so leaked nodes would take a new chunk.
+  for _ in 0..5 {
+    let result = catch_unwind(AssertUnwindSafe(|| {
+      map.insert(Fragile::panicking_at(2, 2), 0)
+    }));
+    assert!(result.is_err());
+  }
+  assert_eq!(map.stats().arena_bytes, reserved);
+  assert_eq!(map.len(), 1);
+}
@@ -0,0 +1,164 @@
+use art_rs::map::{ARTMap, ARTStats};
+
+/// Returns counts of Node4, Node16, Node48 and Node256.
+fn classes(stats: &ARTStats) -> [usize; 4] {
+  [
+    stats.node4.count,
+    stats.node16.count,
+    stats.node48.count,
+    stats.node256.count,
+  ]
+}
+
+/// Expected classes of a tree whose only internal node has `children` children.
+fn single_node(children: usize) -> [usize; 4] {
+  match children {
+    0 => [0; 4],
+    1..=4 => [1, 0, 0, 0],
+    5..=16 => [0, 1, 0, 0],
+    17..=48 => [0, 0, 1, 0],
+    _ => [0, 0, 0, 1],
+  }
+}
+
+fn assert_map(map: &ARTMap<Vec<u8>, usize>, expected: &[(Vec<u8>, usize)]) {
+  #[cfg(feature = "debug-invariants")]
+  map.validate().unwrap();
+  assert_eq!(map.len(), expected.len());
+  assert!(map.iter().eq(expected.iter().map(|(k, v)| (k, v))));
+  for (k, v) in expected {
+    assert_eq!(map.get(k), Some(v));
+  }
+}
+
+/// Whether to check the whole map at `len` entries. Miri only checks around class boundaries.
+fn check_at(len: usize) -> bool {
+  !cfg!(miri) || [0, 1, 2, 4, 5, 16, 17, 48, 49, 256].contains(&len)
+}
+
+#[test]
+fn grow() {
+  // Keys share the prefix, so they are children of a single node.
+  let mut map = ARTMap::new();
+  let mut expected = vec![];
+  for i in 0..=255u8 {
+    let key = vec![b'a', b'b', i];
+    assert_eq!(map.insert(key.clone(), i as usize), None);
+    expected.push((key, i as usize));
+
+    let stats = map.stats();
+    let children = if i == 0 { 0 } else { i as usize + 1 };
+    assert_eq!(classes(&stats), single_node(children), "{} keys", i + 1);
+    assert_eq!(stats.leaves, i as usize + 1);
+    if check_at(expected.len()) {
+      assert_map(&map, &expected);
+    }
+  }
+}
+
+#[test]
+fn grow_with_leaf_slot() {
+  // Growing keeps partial key and leaf slot of the node.
+  let mut map = ARTMap::new();
+  let mut expected = vec![(b"abc".to_vec(), 0)];
+  map.insert(b"abc".to_vec(), 0);
+  for i in 0..=255u8 {
+    let key = vec![b'a', b'b', b'c', i];
+    map.insert(key.clone(), i as usize + 1);
+    expected.push((key, i as usize + 1));
+
+    let stats = map.stats();
+    assert_eq!(classes(&stats), single_node(i as usize + 1));
+    assert_eq!(stats.partial_key_lens.get(&3), Some(&1));
+    if check_at(expected.len() - 1) {
+      assert_map(&map, &expected);
+    }
+  }
+}
+
+#[test]
+fn shrink() {
+  let mut map = ARTMap::new();
+  let mut expected: Vec<_> = (0..=255u8).map(|i| (vec![b'a', i], i as usize)).collect();
+  map.extend(expected.iter().cloned());
+  // Remove from both ends and the middle, so that every child index is hit.
+  while !expected.is_empty() {
+    let i = match expected.len() % 3 {
+      0 => 0,
+      1 => expected.len() - 1,
+      _ => expected.len() / 2,
+    };
+    let (key, v) = expected.remove(i);
+    assert_eq!(map.remove(&key), Some(v));
+    assert_eq!(map.remove(&key), None);
+
+    let stats = map.stats();
+    let children = if expected.len() > 1 {
+      expected.len()
+    } else {
+      0
+    };
+    assert_eq!(
+      classes(&stats),
+      single_node(children),
+      "{} keys",
+      expected.len()
+    );
+    if check_at(expected.len()) {
+      assert_map(&map, &expected);
+    }
+  }
+  assert_eq!(
+    map.stats(),
+    ARTStats {
+      arena_bytes: map.stats().arena_bytes,
+      ..ARTStats::default()
+    }
+  );
+}
+
+#[test]
+fn merge() {
+  let mut map = ARTMap::new();
+  for (i, key) in ["abc1x", "abc1y", "abc2", "abc"].iter().enumerate() {
+    map.insert(key.as_bytes().to_vec(), i);
+  }
+  assert_eq!(classes(&map.stats()), [2, 0, 0, 0]);
+
+  // Node "abc" has leaf slot and child '1' left, so it's kept.
+  assert_eq!(map.remove(&b"abc2"[..]), Some(2));
+  assert_eq!(classes(&map.stats()), [2, 0, 0, 0]);
+  // Node "1" is merged into its parent, which is left with only it.
+  assert_eq!(map.remove(&b"abc"[..]), Some(3));
+  let stats = map.stats();
+  assert_eq!(classes(&stats), [1, 0, 0, 0]);
+  assert_eq!(stats.partial_key_lens.get(&4), Some(&1));
+  assert_map(&map, &[(b"abc1x".to_vec(), 0), (b"abc1y".to_vec(), 1)]);
+
+  assert_eq!(map.remove(&b"abc1y"[..]), Some(1));
+  assert_eq!(classes(&map.stats()), [0; 4]);
+  assert_map(&map, &[(b"abc1x".to_vec(), 0)]);
+}
+
+#[test]
+fn merge_truncated() {
+  // Merging partial keys of 10 bytes, 1 byte and 10 bytes truncates the result.
+  let prefix = [b'p'; 10];
+  let key = |tail: &[u8]| [&prefix[..], tail].concat();
+  let mut map = ARTMap::new();
+  map.insert(key(b"a0123456789x"), 0);
+  map.insert(key(b"a0123456789y"), 1);
+  map.insert(key(b"b"), 2);
+  assert_eq!(map.stats().truncated_partial_keys, 0);
+
+  assert_eq!(map.remove(&key(b"b")), Some(2));
+  let stats = map.stats();
+  assert_eq!(classes(&stats), [1, 0, 0, 0]);
+  assert_eq!(stats.truncated_partial_keys, 1);
+  assert_map(
+    &map,
+    &[(key(b"a0123456789x"), 0), (key(b"a0123456789y"), 1)],
+  );
+  assert_eq!(map.get(&key(b"a0123456789")), None);
+  assert_eq!(map.get(&key(b"a012345678zx")), None);
+}
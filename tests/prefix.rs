@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+
+use proptest::collection::{btree_map, vec};
+use proptest::prelude::*;
+
+use art_rs::map::ARTMap;
+
+fn key() -> impl Strategy<Value = Vec<u8>> {
+  prop_oneof![
+    // Small alphabet, so keys share prefixes and are prefixes of each other.
+    vec(0..3u8, 0..6),
+    // Long shared prefix, so partial keys are truncated and prefixes may end or diverge at bytes
+    // which are not stored in nodes.
+    (0..40usize, vec(0..3u8, 0..3)).prop_map(|(len, tail)| [vec![7; len], tail].concat()),
+  ]
+}
+
+fn with_prefix<'a>(
+  expected: &'a BTreeMap<Vec<u8>, u32>,
+  prefix: &'a [u8],
+) -> impl DoubleEndedIterator<Item = (&'a Vec<u8>, &'a u32)> {
+  expected.iter().filter(move |(k, _)| k.starts_with(prefix))
+}
+
+proptest! {
+  #[test]
+  fn prefix(expected in btree_map(key(), any::<u32>(), 0..100), prefix in key()) {
+    let mut map: ARTMap<_, _> = expected.clone().into_iter().collect();
+    prop_assert!(map.prefix_iter(&prefix).eq(with_prefix(&expected, &prefix)));
+    prop_assert!(map.prefix_iter(&prefix).rev().eq(with_prefix(&expected, &prefix).rev()));
+    prop_assert_eq!(map.has_prefix(&prefix), with_prefix(&expected, &prefix).next().is_some());
+    prop_assert_eq!(map.first_with_prefix(&prefix), with_prefix(&expected, &prefix).next());
+    prop_assert_eq!(map.last_with_prefix(&prefix), with_prefix(&expected, &prefix).next_back());
+
+    for (k, v) in map.prefix_iter_mut(&prefix) {
+      *v = v.wrapping_add(k.len() as u32);
+    }
+    let mut expected = expected;
+    for (k, v) in expected.iter_mut().filter(|(k, _)| k.starts_with(&prefix)) {
+      *v = v.wrapping_add(k.len() as u32);
+    }
+    prop_assert!(map.iter().eq(expected.iter()));
+  }
+}
+
+#[test]
+fn prefix_of_partial_key() {
+  let mut map = ARTMap::new();
+  for key in ["romane", "romanus", "romulus", "rubens", "ruber", "rubicon"] {
+    map.insert(key.to_string(), key.len());
+  }
+  let keys = |prefix: &str| {
+    map
+      .prefix_iter(prefix.as_bytes())
+      .map(|(k, _)| k.as_str())
+      .collect::<Vec<_>>()
+  };
+  assert_eq!(keys(""), map.keys().map(String::as_str).collect::<Vec<_>>());
+  assert_eq!(keys("r"), keys(""));
+  // Ends inside partial key "oman" and "ube".
+  assert_eq!(keys("rom"), ["romane", "romanus", "romulus"]);
+  assert_eq!(keys("roma"), ["romane", "romanus"]);
+  assert_eq!(keys("rub"), ["rubens", "ruber", "rubicon"]);
+  assert_eq!(keys("rube"), ["rubens", "ruber"]);
+  // Diverges inside partial key, or extends past a leaf.
+  assert!(keys("romx").is_empty());
+  assert!(keys("rubiconx").is_empty());
+  assert_eq!(keys("rubicon"), ["rubicon"]);
+  assert!(!map.has_prefix(b"x"));
+  assert_eq!(
+    map.first_with_prefix(b"rube"),
+    Some((&"rubens".to_string(), &6))
+  );
+  assert_eq!(
+    map.last_with_prefix(b"rom"),
+    Some((&"romulus".to_string(), &7))
+  );
+
+  let empty = ARTMap::<String, usize>::new();
+  assert_eq!(empty.prefix_iter(b"").next(), None);
+  assert!(!empty.has_prefix(b""));
+  assert_eq!(empty.first_with_prefix(b""), None);
+}
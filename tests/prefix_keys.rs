@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::ops::Bound::{Excluded, Included, Unbounded};
+
+use art_rs::map::ARTMap;
+
+fn assert_same(map: &ARTMap<String, usize>, expected: &BTreeMap<String, usize>) {
+  assert_eq!(map.len(), expected.len());
+  assert!(map.iter().eq(expected.iter()));
+  assert!(map.iter().rev().eq(expected.iter().rev()));
+  for (k, v) in expected {
+    assert_eq!(map.get(k.as_str()), Some(v), "key {:?}", k);
+  }
+}
+
+#[test]
+fn empty_key() {
+  let mut map = ARTMap::new();
+  assert_eq!(map.get(""), None);
+  assert_eq!(map.insert(String::new(), 0), None);
+  assert_eq!(map.get(""), Some(&0));
+  assert_eq!(map.insert(String::new(), 1), Some(0));
+
+  map.insert("a".to_string(), 2);
+  map.insert("b".to_string(), 3);
+  assert_eq!(map.get(""), Some(&1));
+  assert_eq!(map.first_key_value(), Some((&String::new(), &1)));
+  assert_eq!(map.insert(String::new(), 4), Some(1));
+  assert_eq!(map.len(), 3);
+
+  assert_eq!(map.remove(""), Some(4));
+  assert_eq!(map.remove(""), None);
+  assert_eq!(map.get("a"), Some(&2));
+  assert_eq!(map.get("b"), Some(&3));
+  assert_eq!(map.len(), 2);
+}
+
+#[test]
+fn prefix_chain() {
+  let keys = ["", "a", "ab", "abc", "abcd"];
+  for order in [[0, 1, 2, 3, 4], [4, 3, 2, 1, 0], [2, 4, 0, 3, 1]] {
+    let mut map = ARTMap::new();
+    let mut expected = BTreeMap::new();
+    for (v, &i) in order.iter().enumerate() {
+      assert_eq!(map.insert(keys[i].to_string(), v), None);
+      expected.insert(keys[i].to_string(), v);
+      assert_same(&map, &expected);
+    }
+    for (v, &i) in order.iter().enumerate() {
+      assert_eq!(map.insert(keys[i].to_string(), v + 10), Some(v));
+      expected.insert(keys[i].to_string(), v + 10);
+    }
+    assert_same(&map, &expected);
+    assert_eq!(map.prefix_iter(b"ab").count(), 3);
+    assert_eq!(
+      map
+        .range::<str, _>((Included("a"), Excluded("abc")))
+        .count(),
+      2
+    );
+
+    for &i in order.iter().rev() {
+      assert_eq!(map.remove(keys[i]), expected.remove(keys[i]));
+      assert_eq!(map.remove(keys[i]), None);
+      assert_same(&map, &expected);
+    }
+    assert!(map.is_empty());
+  }
+}
+
+#[test]
+fn split_points() {
+  let mut map = ARTMap::new();
+  let mut expected = BTreeMap::new();
+  // "abcdef" and "abcxyz" share partial key "abc", then keys end exactly at the split point and
+  // inside the shared partial key.
+  for (v, key) in ["abcdef", "abcxyz", "abc", "ab", "abcd", "abcdefg", "abcde"]
+    .iter()
+    .enumerate()
+  {
+    assert_eq!(map.insert(key.to_string(), v), None);
+    expected.insert(key.to_string(), v);
+    assert_same(&map, &expected);
+    assert_eq!(map.get("a"), None);
+    assert_eq!(map.get("abcx"), None);
+  }
+  for key in ["abc", "abcdef", "ab", "abcxyz", "abcde", "abcd", "abcdefg"] {
+    assert_eq!(map.remove(key), expected.remove(key));
+    assert_same(&map, &expected);
+  }
+  assert!(map.is_empty());
+}
+
+#[test]
+fn dense_prefixes() {
+  // Every string over a small alphabet up to length 4, so most keys are prefixes of others.
+  let mut keys = vec![String::new()];
+  let mut level = vec![String::new()];
+  for _ in 0..4 {
+    level = level
+      .iter()
+      .flat_map(|p| ["a", "b", "\0"].iter().map(move |c| format!("{}{}", p, c)))
+      .collect();
+    keys.extend(level.iter().cloned());
+  }
+
+  let mut map = ARTMap::new();
+  let mut expected = BTreeMap::new();
+  let mut state = 17usize;
+  let mut next = || {
+    state = state
+      .wrapping_mul(6364136223846793005)
+      .wrapping_add(1442695040888963407);
+    state >> 33
+  };
+  for _ in 0..if cfg!(miri) { 200 } else { 2000 } {
+    let key = &keys[next() % keys.len()];
+    if next() % 3 == 0 {
+      assert_eq!(map.remove(key.as_str()), expected.remove(key));
+    } else {
+      let v = next();
+      assert_eq!(map.insert(key.clone(), v), expected.insert(key.clone(), v));
+    }
+  }
+  assert_same(&map, &expected);
+  for key in &keys {
+    assert_eq!(
+      map.prefix_iter(key.as_bytes()).count(),
+      expected
+        .keys()
+        .filter(|k| k.starts_with(key.as_str()))
+        .count()
+    );
+    let bounds = (Excluded(key.as_str()), Unbounded);
+    assert!(map
+      .range::<str, _>(bounds)
+      .eq(expected.range::<str, _>(bounds)));
+    let bounds = (Unbounded, Included(key.as_str()));
+    assert!(map
+      .range::<str, _>(bounds)
+      .rev()
+      .eq(expected.range::<str, _>(bounds).rev()));
+  }
+}
+
+#[test]
+fn long_partial_keys() {
+  // Partial keys of root and nodes below hosts are longer than what's stored inline.
+  let hosts = ["https://www.example.com/", "https://www.example.org/"];
+  let paths = ["assets/static/images/", "assets/static/scripts/", "api/v1/users/"];
+  let mut keys = Vec::new();
+  for host in hosts {
+    for path in paths {
+      for i in 0..20 {
+        keys.push(format!("{}{}{}", host, path, i));
+      }
+      keys.push(format!("{}{}", host, path));
+    }
+  }
+
+  let mut map = ARTMap::new();
+  let mut expected = BTreeMap::new();
+  for (v, key) in keys.iter().enumerate() {
+    assert_eq!(map.insert(key.clone(), v), None);
+    expected.insert(key.clone(), v);
+  }
+  assert_same(&map, &expected);
+  assert!(map.stats().truncated_partial_keys > 0);
+
+  // Keys diverging from existing ones inside long partial keys, some at bytes which are not stored.
+  for key in [
+    "https://www.examqle.com/assets/static/images/1",
+    "https://www.example.com/assets/static/imagez/1",
+    "https://www.example.com/assets/static/images",
+    "https://www.example.com/api/v1/users",
+    "https://www.example.org/assets",
+  ] {
+    assert_eq!(map.get(key), None, "key {:?}", key);
+    assert_eq!(map.insert(key.to_string(), 0), None);
+    expected.insert(key.to_string(), 0);
+    assert_eq!(map.get(key), Some(&0));
+  }
+  assert_same(&map, &expected);
+  assert_eq!(
+    map.prefix_iter(b"https://www.example.com/assets/").count(),
+    expected
+      .keys()
+      .filter(|k| k.starts_with("https://www.example.com/assets/"))
+      .count()
+  );
+  assert_eq!(map.prefix_iter(b"https://www.examqle.com/videos/").count(), 0);
+  let bounds = (Excluded("https://www.example.com/assets/static/imagea"), Unbounded);
+  assert!(map
+    .range::<str, _>(bounds)
+    .eq(expected.range::<str, _>(bounds)));
+
+  for key in &keys {
+    assert_eq!(map.remove(key.as_str()), expected.remove(key));
+  }
+  assert_same(&map, &expected);
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use proptest::collection::{btree_map, vec};
+use proptest::prelude::*;
+
+use art_rs::map::ARTMap;
+
+fn key() -> impl Strategy<Value = Vec<u8>> {
+  // Small alphabet, so bounds often hit keys, their prefixes and partial keys.
+  vec(0..4u8, 0..6)
+}
+
+fn bound(kind: u8, key: &[u8]) -> Bound<&[u8]> {
+  match kind % 3 {
+    0 => Included(key),
+    1 => Excluded(key),
+    _ => Unbounded,
+  }
+}
+
+/// Returns whether `range` panics for a non-empty map.
+fn is_invalid(start: Bound<&[u8]>, end: Bound<&[u8]>) -> bool {
+  match (start, end) {
+    (Excluded(s), Excluded(e)) => s >= e,
+    (Included(s) | Excluded(s), Included(e) | Excluded(e)) => s > e,
+    _ => false,
+  }
+}
+
+/// Maximum number of entries.
+const MAX_LEN: usize = if cfg!(miri) { 20 } else { 100 };
+
+proptest! {
+  #[test]
+  fn range(
+    expected in btree_map(key(), any::<u32>(), 0..MAX_LEN),
+    (start, end) in (key(), key()),
+    (start_kind, end_kind) in (0..3u8, 0..3u8),
+    from_back in vec(any::<bool>(), 1..8),
+  ) {
+    let mut map: ARTMap<_, _> = expected.clone().into_iter().collect();
+    let bounds = (bound(start_kind, &start), bound(end_kind, &end));
+    if is_invalid(bounds.0, bounds.1) {
+      let panics = catch_unwind(AssertUnwindSafe(|| map.range::<[u8], _>(bounds).count())).is_err();
+      prop_assert_eq!(panics, !expected.is_empty());
+      let panics =
+        catch_unwind(AssertUnwindSafe(|| map.range_mut::<[u8], _>(bounds).count())).is_err();
+      prop_assert_eq!(panics, !expected.is_empty());
+      return Ok(());
+    }
+
+    let expected_range: Vec<_> = expected.range::<[u8], _>(bounds).collect();
+    prop_assert!(map.range::<[u8], _>(bounds).eq(expected_range.iter().copied()));
+    prop_assert!(map.range::<[u8], _>(bounds).rev().eq(expected_range.iter().rev().copied()));
+
+    // Take entries from both ends until they meet.
+    let mut range = map.range::<[u8], _>(bounds);
+    let (mut front, mut back) = (vec![], vec![]);
+    for &from_back in from_back.iter().cycle() {
+      let item = if from_back { range.next_back() } else { range.next() };
+      match item {
+        Some(item) if from_back => back.push(item),
+        Some(item) => front.push(item),
+        None => break,
+      }
+    }
+    prop_assert!(range.next().is_none());
+    front.extend(back.into_iter().rev());
+    prop_assert_eq!(front, expected_range);
+
+    for (k, v) in map.range_mut::<[u8], _>(bounds) {
+      *v = v.wrapping_add(k.len() as u32);
+    }
+    let mut expected = expected;
+    for (k, v) in expected.range_mut::<[u8], _>(bounds) {
+      *v = v.wrapping_add(k.len() as u32);
+    }
+    prop_assert!(map.iter().eq(expected.iter()));
+  }
+}
+
+#[test]
+fn bounds() {
+  let len = if cfg!(miri) { 100 } else { 1000 };
+  let map: ARTMap<u32, u32> = (0..len).map(|i| (i * 3, i)).collect();
+  let expected: BTreeMap<u32, u32> = (0..len).map(|i| (i * 3, i)).collect();
+  // Bounds on keys, between keys and outside of keys.
+  let last = (len - 1) * 3;
+  let keys = [0, 1, 2, 3, 299, 300, 301, last, last + 1, 5000];
+  for &s in &keys {
+    for &e in &keys {
+      for start in [Included(&s), Excluded(&s), Unbounded] {
+        for end in [Included(&e), Excluded(&e), Unbounded] {
+          let valid = match (start, end) {
+            (Excluded(s), Excluded(e)) => s < e,
+            (Included(s) | Excluded(s), Included(e) | Excluded(e)) => s <= e,
+            _ => true,
+          };
+          if valid {
+            assert!(
+              map.range((start, end)).eq(expected.range((start, end))),
+              "{:?}",
+              (start, end)
+            );
+          }
+        }
+      }
+    }
+  }
+  assert!(map.range(..).eq(expected.iter()));
+  assert!(map.range(3..=300).eq(expected.range(3..=300)));
+  assert!(map.range(..301).rev().eq(expected.range(..301).rev()));
+}
+
+#[test]
+#[should_panic(expected = "range start is greater than range end")]
+fn start_greater_than_end() {
+  let map: ARTMap<u32, u32> = (0..10).map(|i| (i, i)).collect();
+  map.range((Included(5), Excluded(4))).count();
+}
+
+#[test]
+#[should_panic(expected = "range start and end are equal and excluded")]
+fn equal_excluded() {
+  let map: ARTMap<u32, u32> = (0..10).map(|i| (i, i)).collect();
+  map.range((Excluded(5), Excluded(5))).count();
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "serde")]
+
+use std::sync::OnceLock;
+
+use serde_test::{assert_de_tokens, assert_tokens, Token};
+
+use art_rs::map::ARTMap;
+use art_rs::set::ARTSet;
+
+#[test]
+fn map_tokens() {
+  let map: ARTMap<String, u32> = [("b", 2), ("a", 1), ("ab", 3)]
+    .iter()
+    .map(|&(k, v)| (k.to_string(), v))
+    .collect();
+  assert_tokens(
+    &map,
+    &[
+      Token::Map { len: Some(3) },
+      Token::Str("a"),
+      Token::U32(1),
+      Token::Str("ab"),
+      Token::U32(3),
+      Token::Str("b"),
+      Token::U32(2),
+      Token::MapEnd,
+    ],
+  );
+  assert_tokens(
+    &ARTMap::<String, u32>::new(),
+    &[Token::Map { len: Some(0) }, Token::MapEnd],
+  );
+}
+
+#[test]
+fn map_unsorted_input() {
+  let mut map = ARTMap::new();
+  map.insert(-1i32, 'x');
+  map.insert(0, 'y');
+  map.insert(300, 'z');
+  assert_de_tokens(
+    &map,
+    &[
+      Token::Map { len: None },
+      Token::I32(300),
+      Token::Char('a'),
+      Token::I32(-1),
+      Token::Char('x'),
+      Token::I32(0),
+      Token::Char('y'),
+      Token::I32(300),
+      Token::Char('z'),
+      Token::MapEnd,
+    ],
+  );
+}
+
+#[test]
+fn map_sorted_input() {
+  // Tokens borrow keys for 'static.
+  static SORTED: OnceLock<Vec<String>> = OnceLock::new();
+  let sorted = SORTED.get_or_init(|| {
+    let len = if cfg!(miri) { 200 } else { 2000 };
+    let mut sorted: Vec<_> = (0..len).map(|i: u32| format!("{:x}", i * 7)).collect();
+    sorted.sort();
+    sorted
+  });
+  let mut tokens = vec![Token::Map {
+    len: Some(sorted.len()),
+  }];
+  for (i, key) in sorted.iter().enumerate() {
+    tokens.push(Token::String(key));
+    tokens.push(Token::U64(i as u64));
+  }
+  tokens.push(Token::MapEnd);
+
+  let map: ARTMap<String, u64> = sorted.iter().cloned().zip(0..).collect();
+  assert_tokens(&map, &tokens);
+  for (i, key) in sorted.iter().enumerate() {
+    assert_eq!(map.get(key.as_str()), Some(&(i as u64)));
+  }
+}
+
+#[test]
+fn set_tokens() {
+  let set: ARTSet<u16> = [512, 3, 7].iter().copied().collect();
+  assert_tokens(
+    &set,
+    &[
+      Token::Seq { len: Some(3) },
+      Token::U16(3),
+      Token::U16(7),
+      Token::U16(512),
+      Token::SeqEnd,
+    ],
+  );
+  assert_de_tokens(
+    &set,
+    &[
+      Token::Seq { len: None },
+      Token::U16(7),
+      Token::U16(512),
+      Token::U16(3),
+      Token::U16(7),
+      Token::SeqEnd,
+    ],
+  );
+}
@@ -0,0 +1,100 @@
+use std::cell::Cell;
+use std::collections::BTreeSet;
+
+use proptest::collection::{btree_set, vec};
+use proptest::prelude::*;
+
+use art_rs::key::ARTKey;
+use art_rs::set::ARTSet;
+
+fn elems() -> impl Strategy<Value = BTreeSet<Vec<u8>>> {
+  // Small alphabet, so sets often share elements and elements are prefixes of each other.
+  btree_set(vec(0..3u8, 0..4), 0..30)
+}
+
+/// Checks that `iter` yields `expected`, within bounds of its size hint.
+fn assert_iter<'a, I: Iterator<Item = &'a Vec<u8>>>(iter: I, expected: Vec<&'a Vec<u8>>) {
+  let (lower, upper) = iter.size_hint();
+  let actual: Vec<_> = iter.collect();
+  assert!(lower <= actual.len() && actual.len() <= upper.unwrap());
+  assert_eq!(actual, expected);
+}
+
+proptest! {
+  #[test]
+  fn operations(a in elems(), b in elems()) {
+    let (set_a, set_b): (ARTSet<_>, ARTSet<_>) =
+      (a.iter().cloned().collect(), b.iter().cloned().collect());
+    prop_assert!(set_a.iter().eq(a.iter()));
+
+    assert_iter(set_a.union(&set_b), a.union(&b).collect());
+    assert_iter(set_a.intersection(&set_b), a.intersection(&b).collect());
+    assert_iter(set_a.difference(&set_b), a.difference(&b).collect());
+    assert_iter(set_b.difference(&set_a), b.difference(&a).collect());
+    assert_iter(set_a.symmetric_difference(&set_b), a.symmetric_difference(&b).collect());
+    prop_assert_eq!(set_a.is_subset(&set_b), a.is_subset(&b));
+    prop_assert_eq!(set_a.is_superset(&set_b), a.is_superset(&b));
+    prop_assert_eq!(set_a.is_disjoint(&set_b), a.is_disjoint(&b));
+
+    // A set relates to its own subsets.
+    let sub: ARTSet<_> = a.iter().step_by(2).cloned().collect();
+    prop_assert!(sub.is_subset(&set_a));
+    prop_assert!(set_a.is_superset(&sub));
+    prop_assert_eq!(sub.is_disjoint(&set_a), sub.is_empty());
+  }
+}
+
+thread_local! {
+  static ENCODED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Key counting how many times keys are encoded.
+#[derive(Debug, PartialEq)]
+struct Counted(u32);
+
+impl ARTKey for Counted {
+  type Bytes<'a> = [u8; 4];
+
+  fn to_bytes(&self) -> Self::Bytes<'_> {
+    ENCODED.with(|encoded| encoded.set(encoded.get() + 1));
+    self.0.to_be_bytes()
+  }
+}
+
+#[test]
+fn encode_once() {
+  let a: ARTSet<_> = (0..100).map(|i| Counted(i * 2)).collect();
+  let b: ARTSet<_> = (0..100).map(|i| Counted(i * 3)).collect();
+  // Each element is encoded at most once, however many comparisons it takes part in.
+  let check = |f: &dyn Fn() -> usize, len: usize| {
+    ENCODED.with(|encoded| encoded.set(0));
+    assert_eq!(f(), len);
+    assert!(ENCODED.with(Cell::get) <= a.len() + b.len());
+  };
+  check(&|| a.union(&b).count(), 166);
+  check(&|| a.intersection(&b).count(), 34);
+  check(&|| a.difference(&b).count(), 66);
+  check(&|| a.symmetric_difference(&b).count(), 132);
+}
+
+#[test]
+fn clone_and_debug() {
+  let a: ARTSet<u32> = [1, 2, 3, 5].iter().copied().collect();
+  let b: ARTSet<u32> = [2, 4, 5].iter().copied().collect();
+
+  let mut union = a.union(&b);
+  assert_eq!(union.next(), Some(&1));
+  // A clone continues from the same position, independently of the original.
+  assert!(union.clone().eq(&[2, 3, 4, 5]));
+  assert_eq!(union.next(), Some(&2));
+  assert_eq!(format!("{:?}", union), "Union([3, 4, 5])");
+
+  let intersection = a.intersection(&b);
+  assert!(intersection.clone().eq(intersection));
+  assert_eq!(format!("{:?}", a.intersection(&b)), "Intersection([2, 5])");
+  assert_eq!(format!("{:?}", a.difference(&b)), "Difference([1, 3])");
+  assert_eq!(
+    format!("{:?}", a.symmetric_difference(&b)),
+    "SymmetricDifference([1, 3, 4])"
+  );
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use art_rs::map::{ARTMap, ARTStats};
+
+type Map = ARTMap<Vec<u8>, u64>;
+
+fn map(keys: &[&[u8]]) -> Map {
+  let map: Map = keys.iter().map(|k| (k.to_vec(), 0)).collect();
+  #[cfg(feature = "debug-invariants")]
+  map.validate().unwrap();
+  map
+}
+
+/// Returns counts of Node4, Node16, Node48 and Node256.
+fn classes(stats: &ARTStats) -> [usize; 4] {
+  [
+    stats.node4.count,
+    stats.node16.count,
+    stats.node48.count,
+    stats.node256.count,
+  ]
+}
+
+/// Bytes of one leaf node.
+fn leaf_size(stats: &ARTStats) -> usize {
+  stats.leaf_node_bytes / stats.leaves
+}
+
+#[test]
+fn empty() {
+  assert_eq!(map(&[]).stats(), ARTStats::default());
+}
+
+#[test]
+fn single_leaf() {
+  let stats = map(&[b"abc"]).stats();
+  assert_eq!(classes(&stats), [0; 4]);
+  assert_eq!(stats.leaves, 1);
+  assert_eq!(stats.leaf_depths, [1]);
+  assert!(stats.partial_key_lens.is_empty());
+  assert_eq!(stats.internal_node_bytes, 0);
+  // First chunk of leaves has 4 slots.
+  assert_eq!(stats.arena_bytes, 4 * leaf_size(&stats));
+}
+
+#[test]
+fn two_levels() {
+  // Root branches at 'a' and 'x', and its child has partial key "b" and branches at 'c' and 'd'.
+  let stats = map(&[b"abc", b"abd", b"x"]).stats();
+  assert_eq!(classes(&stats), [2, 0, 0, 0]);
+  assert_eq!(stats.node4.children, 4);
+  assert_eq!(stats.node4.leaf_slots, 0);
+  assert_eq!(stats.leaves, 3);
+  assert_eq!(stats.leaf_depths, [0, 1, 2]);
+  assert_eq!(stats.partial_key_lens, BTreeMap::from([(0, 1), (1, 1)]));
+  assert_eq!(stats.truncated_partial_keys, 0);
+  assert_eq!(stats.internal_node_bytes, stats.node4.bytes);
+  assert_eq!(
+    stats.total_bytes(),
+    stats.node4.bytes + 3 * leaf_size(&stats)
+  );
+  assert_eq!(
+    stats.arena_bytes,
+    4 * (stats.node4.bytes / 2 + leaf_size(&stats))
+  );
+}
+
+#[test]
+fn leaf_slot() {
+  // Root has partial key "a", "a" in its leaf slot, and children at 'b' and 'c'.
+  let stats = map(&[b"a", b"ab", b"ac"]).stats();
+  assert_eq!(classes(&stats), [1, 0, 0, 0]);
+  assert_eq!(stats.node4.children, 2);
+  assert_eq!(stats.node4.leaf_slots, 1);
+  assert_eq!(stats.leaf_depths, [0, 3]);
+  assert_eq!(stats.partial_key_lens, BTreeMap::from([(1, 1)]));
+  assert_eq!(stats.node4.average_fan_out(), 2.0);
+}
+
+#[test]
+fn truncated_partial_key() {
+  let key = |last| {
+    let mut key = vec![7; 20];
+    key.push(last);
+    key
+  };
+  let stats = map(&[&key(1), &key(2)]).stats();
+  assert_eq!(classes(&stats), [1, 0, 0, 0]);
+  assert_eq!(stats.truncated_partial_keys, 1);
+  assert_eq!(stats.partial_key_lens, BTreeMap::from([(20, 1)]));
+  assert_eq!(stats.leaf_depths, [0, 2]);
+}
+
+#[test]
+fn node_classes() {
+  // Children of root at every byte below `n`.
+  let single = |n: u8| {
+    let keys: Vec<[u8; 1]> = (0..n).map(|b| [b]).collect();
+    let keys: Vec<&[u8]> = keys.iter().map(|k| &k[..]).collect();
+    map(&keys).stats()
+  };
+  let leaf = leaf_size(&single(1));
+  let node4 = single(4);
+  assert_eq!(classes(&node4), [1, 0, 0, 0]);
+  assert_eq!(node4.arena_bytes, 4 * (node4.node4.bytes + leaf));
+
+  // Growing from Node4 keeps its chunk reserved, and 5 leaves take a second chunk of 8.
+  let node16 = single(5);
+  assert_eq!(classes(&node16), [0, 1, 0, 0]);
+  assert_eq!(node16.node16.children, 5);
+  assert_eq!(node16.leaf_depths, [0, 5]);
+  assert_eq!(
+    node16.arena_bytes,
+    4 * (node4.node4.bytes + node16.node16.bytes) + 12 * leaf
+  );
+
+  let node48 = single(17);
+  assert_eq!(classes(&node48), [0, 0, 1, 0]);
+  assert_eq!(node48.node48.children, 17);
+  let node256 = single(49);
+  assert_eq!(classes(&node256), [0, 0, 0, 1]);
+  assert_eq!(node256.node256.children, 49);
+  assert_eq!(node256.leaf_depths, [0, 49]);
+  assert_eq!(node256.internal_node_bytes, node256.node256.bytes);
+  assert!(node256.node256.bytes > node48.node48.bytes);
+  assert!(node48.node48.bytes > node16.node16.bytes);
+  assert!(node16.node16.bytes > node4.node4.bytes);
+}
+
+#[test]
+fn after_removal() {
+  // Node with one entry left merges into its parent, but slabs keep their chunks.
+  let mut map = map(&[b"abc", b"abd", b"x"]);
+  let reserved = map.stats().arena_bytes;
+  map.remove(&b"x"[..]);
+  let stats = map.stats();
+  assert_eq!(classes(&stats), [1, 0, 0, 0]);
+  assert_eq!(stats.partial_key_lens, BTreeMap::from([(2, 1)]));
+  assert_eq!(stats.leaf_depths, [0, 2]);
+  assert_eq!(stats.arena_bytes, reserved);
+}
+
+#[test]
+fn long_partial_key() {
+  // Histogram of partial key lengths only has the lengths present.
+  let key = |last| {
+    let mut key = vec![7; 1 << 16];
+    key.push(last);
+    key
+  };
+  let stats = map(&[&key(1), &key(2)]).stats();
+  assert_eq!(stats.partial_key_lens, BTreeMap::from([(1 << 16, 1)]));
+}
+
+#[test]
+fn key_and_value_bytes() {
+  // Keys of 1, 10 and 100 bytes, whose heap memory isn't part of leaves.
+  let strings: ARTMap<String, u64> = [1, 10, 100].iter().map(|&n| ("k".repeat(n), 0)).collect();
+  let stats = strings.stats();
+  assert_eq!(stats.key_bytes, 111);
+  assert_eq!(stats.inline_value_bytes, 3 * 8);
+  assert!(stats.inline_value_bytes < stats.leaf_node_bytes);
+
+  assert_eq!(map(&[b"abc", b"abd", b"x"]).stats().key_bytes, 7);
+}
@@ -0,0 +1,85 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use art_rs::map::ARTMap;
+
+fn hash<T: Hash>(value: &T) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  value.hash(&mut hasher);
+  hasher.finish()
+}
+
+fn entries() -> impl Strategy<Value = Vec<(u16, u8)>> {
+  // Few distinct keys and values, so maps are often equal or share prefixes.
+  vec((0..8u16, 0..3u8), 0..8)
+}
+
+proptest! {
+  #[test]
+  fn compare(a in entries(), b in entries()) {
+    // Duplicate keys keep the last value, as in `BTreeMap`.
+    let (map_a, map_b): (ARTMap<_, _>, ARTMap<_, _>) =
+      (a.iter().copied().collect(), b.iter().copied().collect());
+    let (expected_a, expected_b): (BTreeMap<_, _>, BTreeMap<_, _>) =
+      (a.into_iter().collect(), b.into_iter().collect());
+    prop_assert!(map_a.iter().eq(expected_a.iter()));
+
+    prop_assert_eq!(map_a == map_b, expected_a == expected_b);
+    prop_assert_eq!(map_a.cmp(&map_b), expected_a.cmp(&expected_b));
+    prop_assert_eq!(map_a.partial_cmp(&map_b), expected_a.partial_cmp(&expected_b));
+    prop_assert_eq!(hash(&map_a), hash(&expected_a));
+    if map_a == map_b {
+      prop_assert_eq!(hash(&map_a), hash(&map_b));
+    }
+    prop_assert_eq!(format!("{:?}", map_a), format!("{:?}", expected_a));
+  }
+}
+
+#[test]
+fn clone() {
+  let mut map: ARTMap<String, Vec<u32>> = (0..100).map(|i| (format!("k{}", i), vec![i])).collect();
+  let cloned = map.clone();
+  assert_eq!(cloned, map);
+  map.get_mut("k1").unwrap().push(1);
+  map.remove("k2");
+  assert_eq!(cloned["k1"], [1]);
+  assert_eq!(cloned["k2"], [2]);
+  assert_ne!(cloned, map);
+  #[cfg(feature = "debug-invariants")]
+  cloned.validate().unwrap();
+
+  let empty = ARTMap::<u32, u32>::default();
+  assert!(empty.clone().is_empty());
+  assert_eq!(empty, ARTMap::new());
+  assert_eq!(format!("{:?}", empty), "{}");
+}
+
+#[test]
+fn partial_ord() {
+  let nan: ARTMap<u8, f64> = [(1, f64::NAN)].iter().copied().collect();
+  assert_ne!(nan, nan);
+  assert_eq!(nan.partial_cmp(&nan), None);
+  let one: ARTMap<u8, f64> = [(1, 1.0)].iter().copied().collect();
+  let two: ARTMap<u8, f64> = [(1, 1.0), (2, 0.0)].iter().copied().collect();
+  assert!(one < two);
+}
+
+#[test]
+fn index_and_extend() {
+  let mut map = ARTMap::new();
+  map.extend([(1u32, 'a'), (2, 'b')]);
+  map.extend([(&2, &'c'), (&3, &'d')]);
+  assert_eq!((map[&1], map[&2], map[&3]), ('a', 'c', 'd'));
+  assert_eq!(map.len(), 3);
+}
+
+#[test]
+#[should_panic(expected = "no entry found for key")]
+fn index_missing() {
+  let map: ARTMap<u32, u32> = (0..10).map(|i| (i, i)).collect();
+  let _ = map[&10];
+}
@@ -0,0 +1,132 @@
+use std::cmp::Reverse;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use art_rs::key::{decode_key, ARTKey, KeyComponent};
+use art_rs::map::ARTMap;
+
+/// Bytes biased towards `0x00` and `0xFF`, which are used by escaping.
+fn bytes() -> impl Strategy<Value = Vec<u8>> {
+  vec(
+    prop_oneof![Just(0u8), Just(0xFF), Just(1), any::<u8>()],
+    0..6,
+  )
+}
+
+fn encode<T: KeyComponent>(key: &T) -> Vec<u8> {
+  let mut out = Vec::new();
+  key.encode(&mut out);
+  out
+}
+
+proptest! {
+  #[test]
+  fn order_bytes(a in (bytes(), any::<u8>(), bytes()), b in (bytes(), any::<u8>(), bytes())) {
+    prop_assert_eq!(a.to_bytes().cmp(&b.to_bytes()), a.cmp(&b));
+    prop_assert_eq!(decode_key::<(Vec<u8>, u8, Vec<u8>)>(&a.to_bytes()), Some(a));
+  }
+
+  #[test]
+  fn order_reverse(
+    a in (bytes(), any::<i16>(), any::<u8>()),
+    b in (bytes(), any::<i16>(), any::<u8>()),
+  ) {
+    let key = |(s, i, u): (Vec<u8>, i16, u8)| (Reverse(s), i, Reverse(u));
+    let (a, b) = (key(a), key(b));
+    prop_assert_eq!(a.to_bytes().cmp(&b.to_bytes()), a.cmp(&b));
+    prop_assert_eq!(Reverse(a.clone()).to_bytes().cmp(&Reverse(b.clone()).to_bytes()), b.cmp(&a));
+    prop_assert_eq!(decode_key::<(Reverse<Vec<u8>>, i16, Reverse<u8>)>(&a.to_bytes()), Some(a));
+  }
+
+  #[test]
+  fn round_trip(key: (String, i64, bool, char, f64, u128)) {
+    let decoded = decode_key::<(String, i64, bool, char, f64, u128)>(&key.to_bytes()).unwrap();
+    prop_assert_eq!(&decoded.0, &key.0);
+    prop_assert_eq!((decoded.1, decoded.2, decoded.3), (key.1, key.2, key.3));
+    prop_assert_eq!(decoded.4.to_bits(), key.4.to_bits());
+    prop_assert_eq!(decoded.5, key.5);
+  }
+
+  #[test]
+  fn round_trip_single(s: String, b in bytes()) {
+    prop_assert_eq!(decode_key::<String>(s.to_bytes()), Some(s.clone()));
+    prop_assert_eq!(decode_key::<Vec<u8>>(b.to_bytes()), Some(b.clone()));
+    let r = Reverse(s);
+    prop_assert_eq!(decode_key::<Reverse<String>>(&r.to_bytes()), Some(r));
+    let r = Reverse(b);
+    prop_assert_eq!(decode_key::<Reverse<Vec<u8>>>(&r.to_bytes()), Some(r));
+  }
+
+  #[test]
+  fn decode_arbitrary(bytes in vec(any::<u8>(), 0..32)) {
+    // Arbitrary input is either rejected, or is the encoding of the decoded key.
+    if let Some(key) = decode_key::<(String, Reverse<Vec<u8>>, u16)>(&bytes) {
+      prop_assert_eq!(key.to_bytes(), bytes);
+    }
+  }
+}
+
+#[test]
+fn escaping() {
+  assert_eq!(encode(&b"a\0b".to_vec()), b"a\0\xFFb\0\0");
+  assert_eq!(encode(&String::new()), b"\0\0");
+  assert_eq!(encode(&Reverse(b"\0".to_vec())), [0xFF, 0x00, 0xFF, 0xFF]);
+
+  // A component which is a prefix of another sorts first, even if the other continues with 0x00.
+  let keys = [
+    ("", 9u8),
+    ("a", 9),
+    ("a\0", 0),
+    ("a\0\0", 0),
+    ("a\u{1}", 0),
+    ("ab", 0),
+    ("abc", 0),
+  ];
+  for pair in keys.windows(2) {
+    let (a, b) = (
+      (pair[0].0.to_string(), pair[0].1),
+      (pair[1].0.to_string(), pair[1].1),
+    );
+    assert!(a.to_bytes() < b.to_bytes(), "{:?} < {:?}", a, b);
+  }
+
+  let map: ARTMap<_, _> = keys
+    .iter()
+    .rev()
+    .map(|&(s, n)| ((s.to_string(), n), ()))
+    .collect();
+  assert!(map
+    .keys()
+    .map(|(s, n)| (s.as_str(), *n))
+    .eq(keys.iter().copied()));
+}
+
+#[test]
+fn malformed() {
+  // Missing terminator.
+  assert_eq!(decode_key::<(Vec<u8>,)>(b"ab"), None);
+  assert_eq!(decode_key::<(Vec<u8>,)>(b"ab\0"), None);
+  // Bad escape.
+  assert_eq!(decode_key::<(Vec<u8>,)>(b"a\0\x01\0\0"), None);
+  // Truncated and trailing bytes.
+  assert_eq!(decode_key::<(u32,)>(&[0, 0, 1]), None);
+  assert_eq!(decode_key::<(u16,)>(&[0, 0, 1]), None);
+  assert_eq!(decode_key::<(Vec<u8>, u8)>(b"a\0\0"), None);
+  // Invalid values.
+  assert_eq!(decode_key::<(String,)>(b"\xFF\0\0"), None);
+  assert_eq!(decode_key::<(bool,)>(&[2]), None);
+  assert_eq!(decode_key::<(char,)>(&0xD800u32.to_be_bytes()), None);
+  // Inverted terminator is not a terminator.
+  assert_eq!(decode_key::<(Reverse<Vec<u8>>,)>(b"a\0\0"), None);
+
+  assert_eq!(
+    decode_key::<(Vec<u8>,)>(b"a\0\xFF\0\0"),
+    Some((b"a\0".to_vec(),))
+  );
+  assert_eq!(decode_key::<u32>(&[0, 0, 1, 0]), Some(256));
+  // Single keys are raw, tuples of one component are escaped.
+  assert_eq!(decode_key::<String>(b"a\0\0"), Some("a\0\0".to_string()));
+  assert_eq!(decode_key::<String>(b"\xFF"), None);
+  assert_eq!(decode_key::<(String,)>(b"a\0\0"), Some(("a".to_string(),)));
+}